@@ -0,0 +1,59 @@
+//! Compares the section scan used by default with the full `Resolve` decode used by
+//! `--structural`.
+//!
+//! ```bash
+//! cargo bench --bench decode -- path/to/large_component.wasm [more.wasm ...]
+//! ```
+
+use std::time::{Duration, Instant};
+
+use wail::{decode_wasm_bytes, process_wasm_bytes};
+
+const ITERATIONS: u32 = 20;
+
+fn time(iterations: u32, mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    start.elapsed() / iterations
+}
+
+fn main() {
+    let paths: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    if paths.is_empty() {
+        eprintln!("usage: cargo bench --bench decode -- <component.wasm>...");
+        return;
+    }
+
+    println!(
+        "{:<40} {:>10} {:>12} {:>12} {:>8}",
+        "component", "size", "scan", "full", "speedup"
+    );
+    for path in paths {
+        let bytes = std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+        // Check once that both paths agree before timing them
+        let scanned = process_wasm_bytes(&path, &bytes).expect("scan failed");
+        let decoded = decode_wasm_bytes(&path, &bytes).expect("decode failed");
+        assert_eq!(scanned.imports.len(), decoded.imports.len());
+        assert_eq!(scanned.exports.len(), decoded.exports.len());
+
+        let scan = time(ITERATIONS, || {
+            process_wasm_bytes(&path, &bytes).unwrap();
+        });
+        let full = time(ITERATIONS, || {
+            decode_wasm_bytes(&path, &bytes).unwrap();
+        });
+        println!(
+            "{:<40} {:>9}K {:>12?} {:>12?} {:>7.1}x",
+            path,
+            bytes.len() / 1024,
+            scan,
+            full,
+            full.as_secs_f64() / scan.as_secs_f64()
+        );
+    }
+}
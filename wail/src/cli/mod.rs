@@ -1,16 +1,25 @@
 use std::path::PathBuf;
 
+use crate::core::{MetadataOutput, StubMode};
+
 #[derive(clap::Parser)]
 #[clap(name = "Wail")]
 pub struct Wail {
-    /// WADM manifest file (optional)
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+
+    /// WADM manifest file, YAML or JSON, or - for stdin (optional)
     #[clap(long = "wadm")]
     pub wadm: Option<PathBuf>,
 
-    /// Components configuration file (optional)
+    /// Components configuration file, YAML or JSON, or - for stdin (optional)
     #[clap(long = "components")]
     pub components: Option<PathBuf>,
 
+    /// Environment overlay from the components file to apply
+    #[clap(long = "env")]
+    pub env: Option<String>,
+
     /// Manifest name
     #[clap(long = "name", default_value = "generated-manifest")]
     pub name: String,
@@ -22,4 +31,206 @@ pub struct Wail {
     /// Manifest description
     #[clap(long = "description", default_value = "Generated by WADM Constructor")]
     pub description: String,
+
+    /// Manifest of a shared application referenced with `application:` (repeatable)
+    #[clap(long = "shared-manifest")]
+    pub shared_manifests: Vec<PathBuf>,
+
+    /// Directory of shared application manifests referenced with `application:`
+    #[clap(long = "manifest-registry")]
+    pub manifest_registry: Option<PathBuf>,
+
+    /// Lint config file setting rule severities (`rules: { <rule>: off|warn|error }`)
+    #[clap(long = "lint-config")]
+    pub lint_config: Option<PathBuf>,
+
+    /// Set a lint rule's severity, as <rule>=<off|warn|error> (repeatable)
+    #[clap(long = "lint")]
+    pub lint: Vec<String>,
+
+    /// Interface alias rules file (`aliases: [{ name, interfaces: [...] }]`), added to the
+    /// `aliases` of the components file
+    #[clap(long = "alias-rules")]
+    pub alias_rules: Option<PathBuf>,
+
+    /// Reject components that are unsigned, have expired claims or an invalid signature
+    #[clap(long = "require-signed")]
+    pub require_signed: bool,
+
+    /// File of accepted issuer account keys, one per line; implies --require-signed
+    #[clap(long = "issuer-allowlist")]
+    pub issuer_allowlist: Option<PathBuf>,
+
+    /// Copy each local component's producers and registry metadata (authors, license, source
+    /// links) into the manifest as annotations or labels
+    #[clap(long = "component-metadata", value_enum)]
+    pub component_metadata: Option<MetadataOutput>,
+
+    /// Synthesize stub components for imports that no component exports. Stubs are unsigned,
+    /// so this cannot be combined with `--require-signed` or `--issuer-allowlist`
+    #[clap(long = "stub-unresolved")]
+    pub stub_unresolved: bool,
+
+    /// Behaviour of generated stub functions. Stubs test wiring only: default mode returns
+    /// fixed all-zero values, which cannot be configured
+    #[clap(long = "stub-mode", value_enum, default_value = "trap")]
+    pub stub_mode: StubMode,
+
+    /// Directory generated stub components are written to
+    #[clap(long = "stub-dir", default_value = "wail-stubs")]
+    pub stub_dir: PathBuf,
+
+    /// Fully decode components and check that linked interfaces agree on their functions
+    #[clap(long = "structural")]
+    pub structural: bool,
+
+    /// Directory decoded component interfaces are cached in, keyed by content hash
+    #[clap(long = "cache-dir", default_value = ".wail/cache")]
+    pub cache_dir: PathBuf,
+
+    /// Decode every component instead of using the on-disk cache
+    #[clap(long = "no-cache")]
+    pub no_cache: bool,
+
+    /// wasm-pkg config resolving `pkg://` sources to registries (defaults to wkg's config)
+    #[clap(long = "pkg-config")]
+    pub pkg_config: Option<PathBuf>,
+
+    /// Write the generated manifest to this file instead of stdout
+    #[clap(long = "output", short = 'o')]
+    pub output: Option<PathBuf>,
+
+    /// Output format of the generated manifest
+    #[clap(long = "format", value_enum, default_value = "wadm")]
+    pub format: OutputFormat,
+
+    /// Namespace of the Kubernetes resources (with --format kubernetes)
+    #[clap(long = "namespace")]
+    pub namespace: Option<String>,
+
+    /// Label added to every Kubernetes resource, as <key>=<value> (repeatable)
+    #[clap(long = "k8s-label")]
+    pub k8s_labels: Vec<String>,
+
+    /// Also emit ConfigMap and Secret stubs for the configs and secrets components reference
+    #[clap(long = "k8s-stubs")]
+    pub k8s_stubs: bool,
+
+    /// Also write a CycloneDX SBOM of the application's components and links to this file
+    #[clap(long = "sbom")]
+    pub sbom: Option<PathBuf>,
+
+    /// Regenerate the manifest whenever an input or component artifact changes
+    #[clap(long = "watch")]
+    pub watch: bool,
+
+    /// Polling interval for --watch, in milliseconds
+    #[clap(long = "watch-interval", default_value = "500")]
+    pub watch_interval: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// The wadm OAM manifest
+    Wadm,
+    /// The application as a resource of the wasmCloud Kubernetes operator
+    Kubernetes,
+}
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Scan a directory for components and wash projects and write a starter components file
+    Init {
+        /// Directory to scan
+        #[clap(default_value = ".")]
+        dir: PathBuf,
+
+        /// Components file to write
+        #[clap(long = "output", short = 'o', default_value = "components.yaml")]
+        output: PathBuf,
+
+        /// Overwrite the output file if it exists
+        #[clap(long = "force")]
+        force: bool,
+    },
+
+    /// Print the interfaces and signing claims of a component
+    Inspect {
+        /// WASM component to inspect
+        path: PathBuf,
+
+        /// Print JSON instead of text
+        #[clap(long = "json")]
+        json: bool,
+
+        /// Fully decode the component and list the functions of each interface
+        #[clap(long = "structural")]
+        structural: bool,
+    },
+
+    /// Generate the manifest, then put it into wadm and deploy it (native build only)
+    Deploy {
+        #[clap(flatten)]
+        lattice: LatticeArgs,
+    },
+
+    /// Generate the manifest and diff it against the version deployed in the lattice (native
+    /// build only)
+    CheckLive {
+        #[clap(flatten)]
+        lattice: LatticeArgs,
+    },
+
+    /// Replay link resolution for a component: each import, the components exporting it,
+    /// explicit links and the rule that picked the target
+    Explain {
+        /// Component to explain
+        component: String,
+
+        /// Only explain this import, as namespace:package/interface
+        interface: Option<String>,
+    },
+
+    /// List the lint rules with their default severities
+    Lints,
+    /// Print the JSON Schema of the components file
+    Schema {
+        /// Write the schema to this file instead of stdout
+        #[clap(long = "output", short = 'o')]
+        output: Option<PathBuf>,
+    },
+}
+
+/// How to reach wadm for `deploy` and `check-live`
+#[derive(clap::Args)]
+pub struct LatticeArgs {
+    /// NATS server wadm listens on, as nats://[token@|user:password@]host[:port]
+    #[clap(long = "nats-url", default_value = "nats://127.0.0.1:4222")]
+    pub nats_url: String,
+
+    /// Lattice the application is deployed to
+    #[clap(long = "lattice", default_value = "default")]
+    pub lattice: String,
+
+    /// How long to wait for each wadm reply, in milliseconds
+    #[clap(long = "timeout", default_value = "5000")]
+    pub timeout: u64,
+}
+
+impl Command {
+    /// Whether the command works on the `--components`/`--wadm` inputs
+    pub fn reads_inputs(&self) -> bool {
+        matches!(
+            self,
+            Command::Deploy { .. } | Command::CheckLive { .. } | Command::Explain { .. }
+        )
+    }
+
+    /// The lattice the command talks to, for commands that generate a manifest first
+    pub fn lattice(&self) -> Option<&LatticeArgs> {
+        match self {
+            Command::Deploy { lattice } | Command::CheckLive { lattice } => Some(lattice),
+            _ => None,
+        }
+    }
 }
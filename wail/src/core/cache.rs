@@ -0,0 +1,539 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::core::fetch::download;
+use crate::core::wasm_pkg::{ImageRef, PulledPackage, WasmPkgConfig};
+use crate::core::{decode_wasm_bytes, process_wasm_bytes};
+use crate::models::{ComponentInfo, EntityKind, PackageRef};
+
+/// Decoded component interfaces keyed by file path.
+///
+/// An entry is reused for as long as the file's modification time and size are unchanged,
+/// so repeated runs in the same process (watch mode) only decode components that changed.
+/// With a cache directory, decoded interfaces are also stored on disk keyed by the SHA-256
+/// of the component, so unchanged components are not decoded again across runs.
+#[derive(Default)]
+pub struct ComponentCache {
+    entries: HashMap<PathBuf, CachedComponent>,
+    /// Directory decoded interfaces are persisted to
+    dir: Option<PathBuf>,
+    /// Fully decode components, recording function names for structural checks
+    structural: bool,
+    /// Every path a decode was requested for, including ones that failed, and tracked paths
+    requested: BTreeSet<PathBuf>,
+    /// Paths decoded since the last call to `take_decoded`
+    decoded: Vec<PathBuf>,
+    /// wasm-pkg config file for `pkg://` sources, else wkg's default
+    pkg_config_path: Option<PathBuf>,
+    /// Loaded on the first `pkg://` source
+    pkg_config: Option<WasmPkgConfig>,
+    /// Kinds inferred from OCI manifests, keyed by image reference
+    image_kinds: HashMap<String, Option<EntityKind>>,
+}
+
+struct CachedComponent {
+    stamp: FileStamp,
+    info: ComponentInfo,
+}
+
+/// Modification time and length of a file, used to detect changes
+pub type FileStamp = (Option<SystemTime>, u64);
+
+/// A cache file. Entries written by another WAIL version or entry format are ignored since
+/// the decoded format may differ.
+#[derive(Serialize, Deserialize)]
+struct DiskEntry {
+    wail: String,
+    format: u32,
+    info: ComponentInfo,
+}
+
+/// Bumped whenever decoding records data that older entries lack
+const ENTRY_FORMAT: u32 = 4;
+
+/// Where a component's interfaces came from
+enum Loaded {
+    Disk(ComponentInfo),
+    Decoded(ComponentInfo),
+}
+
+impl ComponentCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Persists decoded interfaces under `dir`
+    pub fn with_dir(dir: PathBuf) -> Self {
+        Self {
+            dir: Some(dir),
+            ..Self::default()
+        }
+    }
+
+    /// Fully decodes components instead of scanning their import and export sections
+    pub fn structural(mut self, structural: bool) -> Self {
+        self.structural = structural;
+        self
+    }
+
+    /// Reads the wasm-pkg config from `path` instead of wkg's default location
+    pub fn pkg_config(mut self, path: Option<PathBuf>) -> Self {
+        self.pkg_config_path = path;
+        self
+    }
+
+    /// Returns the interfaces of the WASM file at `path`, decoding it only if it changed
+    pub fn process_wasm_file(&mut self, name: &str, path: &Path) -> anyhow::Result<ComponentInfo> {
+        self.requested.insert(path.to_path_buf());
+        let stamp = file_stamp(path)?;
+
+        if let Some(entry) = self.entries.get(path) {
+            if entry.stamp == stamp {
+                println!(
+                    "Reusing decoded interfaces for {}: {}",
+                    name,
+                    path.display()
+                );
+                return Ok(entry.info.clone());
+            }
+        }
+
+        let loaded = load(name, path, self.dir.as_deref(), self.structural)?;
+        Ok(self.insert(path, stamp, loaded))
+    }
+
+    /// Loads every component in `components` that is not cached yet, decoding them
+    /// concurrently in native builds. Failures are left for `process_wasm_file` to report.
+    pub fn prefetch(&mut self, components: &[(String, PathBuf)]) {
+        let mut seen = HashSet::new();
+        let pending: Vec<_> = components
+            .iter()
+            .filter(|(_, path)| seen.insert(path))
+            .filter_map(|(name, path)| {
+                let stamp = file_stamp(path).ok()?;
+                let fresh = self
+                    .entries
+                    .get(path)
+                    .is_some_and(|entry| entry.stamp == stamp);
+                (!fresh).then_some((name.as_str(), path.as_path(), stamp))
+            })
+            .collect();
+        if pending.len() < 2 {
+            return;
+        }
+
+        println!("Loading {} components", pending.len());
+        let dir = self.dir.as_deref();
+        let loaded = load_all(&pending, dir, self.structural);
+        for ((_, path, stamp), loaded) in pending.iter().zip(loaded) {
+            if let Ok(loaded) = loaded {
+                self.requested.insert(path.to_path_buf());
+                self.insert(path, *stamp, loaded);
+            }
+        }
+    }
+
+    /// Downloads the component at `url` unless a download matching `sha256` is already
+    /// stored, and returns the local copy. Downloads are named after their digest.
+    pub fn download(&mut self, name: &str, url: &str, sha256: &str) -> anyhow::Result<PathBuf> {
+        let dir = self.store_dir("downloads");
+        let path = dir.join(format!("{}.wasm", sha256));
+        if std::fs::read(&path).is_ok_and(|bytes| format!("{:x}", Sha256::digest(&bytes)) == sha256)
+        {
+            println!("Using downloaded {}: {}", name, url);
+            return Ok(path);
+        }
+
+        println!("Downloading {}: {}", name, url);
+        let bytes = download(url)?;
+        let digest = format!("{:x}", Sha256::digest(&bytes));
+        if digest != sha256 {
+            anyhow::bail!(
+                "{} does not match the declared sha256: expected {}, downloaded {}",
+                url,
+                sha256,
+                digest
+            );
+        }
+        std::fs::create_dir_all(&dir)
+            .and_then(|_| std::fs::write(&path, &bytes))
+            .with_context(|| format!("failed to store the download in {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Pulls `package` from its wasm-pkg registry unless it was pulled before, and returns
+    /// the local copy with the OCI reference it came from
+    pub fn pull_package(
+        &mut self,
+        name: &str,
+        package: &PackageRef,
+    ) -> anyhow::Result<PulledPackage> {
+        let dir = self.store_dir("packages");
+        println!("Resolving {}: pkg://{}", name, package);
+        self.wasm_pkg_config()?.pull(package, &dir)
+    }
+
+    /// Infers whether the OCI image `image` is a capability provider or a component from
+    /// the media types of its manifest, using the registry credentials of the wasm-pkg config.
+    /// The manifest is fetched once per reference; kinds of digest-pinned images never
+    /// change, so they are also stored on disk.
+    pub fn image_kind(
+        &mut self,
+        name: &str,
+        image: &ImageRef,
+    ) -> anyhow::Result<Option<EntityKind>> {
+        let key = image.to_string();
+        if let Some(kind) = self.image_kinds.get(&key) {
+            return Ok(*kind);
+        }
+        let stored = image
+            .digest()
+            .map(|digest| self.store_dir("kinds").join(digest.replace(':', "-")));
+        if let Some(kind) = stored.as_deref().and_then(read_kind) {
+            println!("Using the cached kind of {}: {}", name, image);
+            self.image_kinds.insert(key, Some(kind));
+            return Ok(Some(kind));
+        }
+
+        println!("Inspecting the OCI manifest of {}: {}", name, image);
+        let kind = self.wasm_pkg_config()?.image_kind(image)?;
+        if let (Some(path), Some(kind)) = (&stored, kind) {
+            // The cache is an optimisation, so failing to write it is not an error
+            if let Err(e) = write_kind(path, kind) {
+                println!("Not caching the kind of {}: {}", image, e);
+            }
+        }
+        self.image_kinds.insert(key, kind);
+        Ok(kind)
+    }
+
+    fn wasm_pkg_config(&mut self) -> anyhow::Result<&WasmPkgConfig> {
+        if self.pkg_config.is_none() {
+            self.pkg_config = Some(WasmPkgConfig::load(self.pkg_config_path.as_deref())?);
+        }
+        Ok(self.pkg_config.as_ref().expect("config loaded above"))
+    }
+
+    /// Where fetched artifacts are stored: the cache directory, or the system temporary
+    /// directory without one
+    fn store_dir(&self, kind: &str) -> PathBuf {
+        match &self.dir {
+            Some(dir) => dir.join(kind),
+            None => std::env::temp_dir().join(format!("wail-{}", kind)),
+        }
+    }
+
+    /// Records a file decoded interfaces depend on without being decoded themselves, such as
+    /// a project manifest, so `--watch` notices when it changes or appears
+    pub fn track(&mut self, path: &Path) {
+        self.requested.insert(path.to_path_buf());
+    }
+
+    /// All paths a decode has been requested for
+    pub fn requested_paths(&self) -> impl Iterator<Item = &Path> {
+        self.requested.iter().map(PathBuf::as_path)
+    }
+
+    /// Returns and clears the list of paths decoded since the previous call
+    pub fn take_decoded(&mut self) -> Vec<PathBuf> {
+        std::mem::take(&mut self.decoded)
+    }
+
+    fn insert(&mut self, path: &Path, stamp: FileStamp, loaded: Loaded) -> ComponentInfo {
+        let info = match loaded {
+            Loaded::Disk(info) => info,
+            Loaded::Decoded(info) => {
+                self.decoded.push(path.to_path_buf());
+                info
+            }
+        };
+        self.entries.insert(
+            path.to_path_buf(),
+            CachedComponent {
+                stamp,
+                info: info.clone(),
+            },
+        );
+        info
+    }
+}
+
+#[cfg(feature = "native")]
+fn load_all(
+    pending: &[(&str, &Path, FileStamp)],
+    dir: Option<&Path>,
+    structural: bool,
+) -> Vec<anyhow::Result<Loaded>> {
+    let workers = std::thread::available_parallelism()
+        .map(usize::from)
+        .unwrap_or(1)
+        .min(pending.len());
+    let chunk = pending.len().div_ceil(workers);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = pending
+            .chunks(chunk)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|(name, path, _)| load(name, path, dir, structural))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("decode thread panicked"))
+            .collect()
+    })
+}
+
+/// wasm32-wasip2 has no threads, so the plugin loads components one at a time
+#[cfg(not(feature = "native"))]
+fn load_all(
+    pending: &[(&str, &Path, FileStamp)],
+    dir: Option<&Path>,
+    structural: bool,
+) -> Vec<anyhow::Result<Loaded>> {
+    pending
+        .iter()
+        .map(|(name, path, _)| load(name, path, dir, structural))
+        .collect()
+}
+
+/// Reads the component at `path` from the disk cache, or decodes it and stores the result
+fn load(name: &str, path: &Path, dir: Option<&Path>, structural: bool) -> anyhow::Result<Loaded> {
+    println!("Processing WASM file for {}: {}", name, path.display());
+    let bytes = std::fs::read(path)?;
+    if !bytes.starts_with(b"\0asm") {
+        anyhow::bail!("Not a WASM file: {}", path.display());
+    }
+
+    let decode = if structural {
+        decode_wasm_bytes
+    } else {
+        process_wasm_bytes
+    };
+    let Some(dir) = dir else {
+        return decode(name, &bytes).map(Loaded::Decoded);
+    };
+    // Scanned entries lack function names, so each mode has its own entries
+    let entry_path = dir.join(format!(
+        "{:x}{}.json",
+        Sha256::digest(&bytes),
+        if structural { ".structural" } else { "" }
+    ));
+    if let Some(info) = read_entry(&entry_path) {
+        println!("Using cached interfaces for {}: {}", name, path.display());
+        return Ok(Loaded::Disk(info));
+    }
+
+    let info = decode(name, &bytes)?;
+    // The cache is an optimisation, so failing to write it is not an error
+    if let Err(e) = write_entry(&entry_path, &info) {
+        println!("Not caching {}: {}", path.display(), e);
+    }
+    Ok(Loaded::Decoded(info))
+}
+
+fn read_entry(path: &Path) -> Option<ComponentInfo> {
+    let content = std::fs::read(path).ok()?;
+    let entry: DiskEntry = serde_json::from_slice(&content).ok()?;
+    (entry.wail == env!("CARGO_PKG_VERSION") && entry.format == ENTRY_FORMAT).then_some(entry.info)
+}
+
+fn write_entry(path: &Path, info: &ComponentInfo) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let entry = DiskEntry {
+        wail: env!("CARGO_PKG_VERSION").to_string(),
+        format: ENTRY_FORMAT,
+        info: info.clone(),
+    };
+    // Write to a temporary file first so concurrent writers never leave a partial entry
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+    let temporary = path.with_extension(format!(
+        "{}-{}.tmp",
+        std::process::id(),
+        WRITES.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::write(&temporary, serde_json::to_vec(&entry)?)?;
+    std::fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+fn read_kind(path: &Path) -> Option<EntityKind> {
+    serde_json::from_slice(&std::fs::read(path).ok()?).ok()
+}
+
+fn write_kind(path: &Path, kind: EntityKind) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_vec(&kind)?)?;
+    Ok(())
+}
+
+pub fn file_stamp(path: &Path) -> std::io::Result<FileStamp> {
+    let metadata = std::fs::metadata(path)?;
+    Ok((metadata.modified().ok(), metadata.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use wit_parser::Resolve;
+
+    use super::*;
+
+    const DIGEST: &str = "sha256:0123abcd";
+
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wail-cache-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// A binary WIT package defining `acme:api/handler` with one function per name
+    fn wit_package(functions: &[&str]) -> Vec<u8> {
+        let functions: Vec<_> = functions
+            .iter()
+            .map(|name| format!("{}: func();", name))
+            .collect();
+        let wit = format!(
+            "package acme:api;\ninterface handler {{ {} }}",
+            functions.join(" ")
+        );
+        let mut resolve = Resolve::default();
+        let package = resolve.push_str("api.wit", &wit).unwrap();
+        wit_component::encode(&resolve, package).unwrap()
+    }
+
+    /// The only disk entry in `dir`
+    fn entry_path(dir: &Path) -> PathBuf {
+        let entries: Vec<_> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(entries.len(), 1, "{:?}", entries);
+        entries[0].clone()
+    }
+
+    #[test]
+    fn unchanged_files_are_reused_in_memory() {
+        let dir = cache_dir("memory");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("api.wasm");
+        std::fs::write(&path, wit_package(&["hello"])).unwrap();
+
+        let mut cache = ComponentCache::new();
+        let info = cache.process_wasm_file("api", &path).unwrap();
+        assert_eq!(info.package.unwrap().namespace, "acme");
+        assert_eq!(cache.take_decoded(), std::slice::from_ref(&path));
+        cache.process_wasm_file("api", &path).unwrap();
+        assert!(cache.take_decoded().is_empty());
+
+        // A changed size is a changed file
+        std::fs::write(&path, wit_package(&["hello", "goodbye"])).unwrap();
+        cache.process_wasm_file("api", &path).unwrap();
+        assert_eq!(cache.take_decoded(), std::slice::from_ref(&path));
+        assert_eq!(
+            cache.requested_paths().collect::<Vec<_>>(),
+            [path.as_path()]
+        );
+    }
+
+    #[test]
+    fn decoded_interfaces_are_reused_across_runs() {
+        let dir = cache_dir("disk");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("api.wasm");
+        std::fs::write(&path, wit_package(&["hello"])).unwrap();
+        let entries = dir.join("cache");
+
+        let mut first = ComponentCache::with_dir(entries.clone());
+        first.process_wasm_file("api", &path).unwrap();
+        assert_eq!(first.take_decoded().len(), 1);
+        let entry = entry_path(&entries);
+        assert!(!entry.to_string_lossy().ends_with(".structural.json"));
+
+        let mut second = ComponentCache::with_dir(entries.clone());
+        let info = second.process_wasm_file("api", &path).unwrap();
+        assert_eq!(info.package.unwrap().name, "api");
+        assert!(second.take_decoded().is_empty());
+
+        // Structural decodes do not reuse scanned entries
+        let mut structural = ComponentCache::with_dir(entries.clone()).structural(true);
+        structural.process_wasm_file("api", &path).unwrap();
+        assert_eq!(structural.take_decoded().len(), 1);
+    }
+
+    #[test]
+    fn entries_of_other_formats_are_ignored() {
+        let dir = cache_dir("format");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("api.wasm");
+        std::fs::write(&path, wit_package(&["hello"])).unwrap();
+        let entries = dir.join("cache");
+        ComponentCache::with_dir(entries.clone())
+            .process_wasm_file("api", &path)
+            .unwrap();
+        let entry = entry_path(&entries);
+
+        for (wail, format) in [
+            (env!("CARGO_PKG_VERSION"), ENTRY_FORMAT - 1),
+            ("0.0.0", ENTRY_FORMAT),
+        ] {
+            let mut stored: serde_json::Value =
+                serde_json::from_slice(&std::fs::read(&entry).unwrap()).unwrap();
+            stored["wail"] = wail.into();
+            stored["format"] = format.into();
+            std::fs::write(&entry, serde_json::to_vec(&stored).unwrap()).unwrap();
+
+            let mut cache = ComponentCache::with_dir(entries.clone());
+            cache.process_wasm_file("api", &path).unwrap();
+            assert_eq!(cache.take_decoded().len(), 1, "{} {}", wail, format);
+            assert!(read_entry(&entry).is_some());
+        }
+    }
+
+    /// An image on a local port nothing listens on, so manifest lookups fail fast
+    fn image(reference: &str) -> ImageRef {
+        ImageRef::parse(&format!("oci://127.0.0.1:1/app{}", reference)).unwrap()
+    }
+
+    #[test]
+    fn image_kind_of_digest_reference_is_read_from_disk() {
+        let dir = cache_dir("kind-disk");
+        write_kind(&dir.join("kinds/sha256-0123abcd"), EntityKind::Capability).unwrap();
+        let mut cache = ComponentCache::with_dir(dir);
+        let kind = cache.image_kind("app", &image(&format!("@{}", DIGEST)));
+        assert_eq!(kind.unwrap(), Some(EntityKind::Capability));
+    }
+
+    #[test]
+    fn image_kind_is_memoized() {
+        let dir = cache_dir("kind-memo");
+        write_kind(&dir.join("kinds/sha256-0123abcd"), EntityKind::Component).unwrap();
+        let mut cache = ComponentCache::with_dir(dir.clone());
+        let image = image(&format!("@{}", DIGEST));
+        cache.image_kind("app", &image).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let kind = cache.image_kind("app", &image);
+        assert_eq!(kind.unwrap(), Some(EntityKind::Component));
+    }
+
+    #[test]
+    fn image_kind_lookup_failure_is_an_error() {
+        let dir = cache_dir("kind-error");
+        let mut cache = ComponentCache::with_dir(dir.clone());
+        assert!(cache.image_kind("app", &image(":1.0.0")).is_err());
+        assert!(!dir.join("kinds").exists());
+    }
+}
@@ -0,0 +1,328 @@
+use std::path::Path;
+
+use wadm_types::Properties;
+use wascap::jwt::{validate_token, Claims, Component};
+use wasmparser::{Parser, Payload};
+
+use super::metadata::ANNOTATION_PREFIX;
+use super::{ConstructorManifest, ValidationError, ValidationReport};
+use crate::models::ComponentClaims;
+
+/// Reads the claims `wash build` embeds in the `jwt` custom section of signed components.
+/// Unreadable claims are reported and treated as absent.
+pub fn read_claims(name: &str, bytes: &[u8]) -> Option<ComponentClaims> {
+    let jwt = Parser::new(0)
+        .parse_all(bytes)
+        .filter_map(Result::ok)
+        .find_map(|payload| match payload {
+            Payload::CustomSection(reader) if reader.name() == "jwt" => {
+                Some(String::from_utf8_lossy(reader.data()).to_string())
+            }
+            _ => None,
+        })?;
+
+    let claims = match Claims::<Component>::decode(&jwt) {
+        Ok(claims) => claims,
+        Err(e) => {
+            println!("Ignoring unreadable claims of {}: {}", name, e);
+            return None;
+        }
+    };
+    let signature_valid = validate_token::<Component>(&jwt).is_ok_and(|v| v.signature_valid)
+        // Extraction fails when the component was modified after it was signed
+        && wascap::wasm::extract_claims(bytes).is_ok();
+
+    let metadata = claims.metadata.unwrap_or_default();
+    Some(ComponentClaims {
+        issuer: claims.issuer,
+        subject: claims.subject,
+        name: metadata.name,
+        version: metadata.ver,
+        expires: claims.expires,
+        not_before: claims.not_before,
+        tags: metadata.tags.unwrap_or_default(),
+        signature_valid,
+    })
+}
+
+/// Manifest annotations describing the claims of `component`, keyed
+/// `<prefix>/<component>.claims.<field>`
+pub fn claims_annotations(component: &str, claims: &ComponentClaims) -> Vec<(String, String)> {
+    let key = |field: &str| format!("{}/{}.claims.{}", ANNOTATION_PREFIX, component, field);
+    let mut annotations = vec![
+        (key("issuer"), claims.issuer.clone()),
+        (key("subject"), claims.subject.clone()),
+    ];
+    if let Some(name) = &claims.name {
+        annotations.push((key("name"), name.clone()));
+    }
+    if let Some(version) = &claims.version {
+        annotations.push((key("version"), version.clone()));
+    }
+    if let Some(expires) = claims.expires {
+        annotations.push((key("expires"), expires.to_string()));
+    }
+    if !claims.tags.is_empty() {
+        annotations.push((key("tags"), claims.tags.join(",")));
+    }
+    annotations
+}
+
+/// Signing requirements set with `--require-signed` and `--issuer-allowlist`
+#[derive(Debug, Default)]
+pub struct ClaimsPolicy {
+    require_signed: bool,
+    /// Accepted issuer account keys. An allowlist implies `require_signed`.
+    issuers: Option<Vec<String>>,
+}
+
+impl ClaimsPolicy {
+    /// Loads the allowlist at `allowlist`, one issuer per line with `#` comments
+    pub fn load(require_signed: bool, allowlist: Option<&Path>) -> Result<Self, String> {
+        let issuers = match allowlist {
+            Some(path) => {
+                let content = std::fs::read_to_string(path)
+                    .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+                let issuers: Vec<String> = content
+                    .lines()
+                    .map(|line| line.split('#').next().unwrap_or_default().trim())
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                if issuers.is_empty() {
+                    return Err(format!("{} lists no issuers", path.display()));
+                }
+                Some(issuers)
+            }
+            None => None,
+        };
+        Ok(Self {
+            require_signed: require_signed || issuers.is_some(),
+            issuers,
+        })
+    }
+
+    /// Whether components must be signed
+    pub fn requires_signing(&self) -> bool {
+        self.require_signed
+    }
+
+    /// Checks every component decoded from a local file or a downloaded copy against the
+    /// policy
+    pub fn check(&self, manifest: &ConstructorManifest, report: &mut ValidationReport) {
+        if !self.require_signed {
+            return;
+        }
+
+        for component in &manifest.spec.components {
+            if !matches!(component.properties, Properties::Component { .. }) {
+                continue;
+            }
+            // Only local files and downloads are decoded, other images cannot be checked here
+            if manifest.local_file(&component.name).is_none() {
+                report.add_warning(format!(
+                    "Cannot verify the claims of {}, only local and downloaded components are \
+                     inspected",
+                    component.name
+                ));
+                continue;
+            }
+
+            let claims = manifest
+                .component_interfaces
+                .get(&component.name)
+                .and_then(|info| info.claims.as_ref());
+            let Some(claims) = claims else {
+                report.add_error(ValidationError::ComponentError(format!(
+                    "{} is not signed",
+                    component.name
+                )));
+                continue;
+            };
+            if !claims.signature_valid {
+                report.add_error(ValidationError::ComponentError(format!(
+                    "{} has an invalid signature or was modified after signing",
+                    component.name
+                )));
+            }
+            if claims.is_expired() {
+                report.add_error(ValidationError::ComponentError(format!(
+                    "The claims of {} are expired or not valid yet",
+                    component.name
+                )));
+            }
+            if let Some(issuers) = &self.issuers {
+                if !issuers.contains(&claims.issuer) {
+                    report.add_error(ValidationError::ComponentError(format!(
+                        "{} is signed by {}, which is not in the issuer allowlist",
+                        component.name, claims.issuer
+                    )));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::models::{ComponentInfo, EntityKind};
+
+    const ISSUER: &str = "ACOJJN6WUP4ODD75XEBKKTCCUJJCY5ZKQ56XVKYK4BEJWGVAOOQHZMCW";
+
+    fn allowlist(name: &str, content: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("wail-claims-{}-{}", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn claims(issuer: &str) -> ComponentClaims {
+        ComponentClaims {
+            issuer: issuer.to_string(),
+            subject: "MBCFOPM6JW2APJLXJD3Z5O4CN7CPYJ2B4FTKLJUR5YR5MITIU7HD3WD5".to_string(),
+            name: None,
+            version: None,
+            expires: None,
+            not_before: None,
+            tags: Vec::new(),
+            signature_valid: true,
+        }
+    }
+
+    /// Adds `name` as a component decoded from a local copy
+    fn add(manifest: &mut ConstructorManifest, name: &str, claims: Option<ComponentClaims>) {
+        let info = ComponentInfo {
+            imports: Vec::new(),
+            exports: Vec::new(),
+            package: None,
+            claims,
+            producers: Default::default(),
+            registry: None,
+            version: None,
+        };
+        manifest
+            .merge_component_info(
+                name.to_string(),
+                info,
+                PathBuf::from(format!("https://example.com/{}.wasm", name)),
+                EntityKind::Component,
+            )
+            .unwrap();
+        manifest
+            .local_copies
+            .insert(name.to_string(), PathBuf::from(format!("{}.wasm", name)));
+    }
+
+    fn check(policy: &ClaimsPolicy, manifest: &ConstructorManifest) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        policy.check(manifest, &mut report);
+        report
+    }
+
+    fn errors(report: &ValidationReport) -> Vec<String> {
+        report.errors.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn allowlist_skips_comments_and_blank_lines() {
+        let path = allowlist(
+            "list",
+            &format!("# Release signers\n\n  {}  # CI\nOTHER\n", ISSUER),
+        );
+        let policy = ClaimsPolicy::load(false, Some(&path)).unwrap();
+        assert!(policy.requires_signing());
+        assert_eq!(
+            policy.issuers.as_deref(),
+            Some(&[ISSUER.to_string(), "OTHER".to_string()][..])
+        );
+    }
+
+    #[test]
+    fn allowlist_without_issuers_is_an_error() {
+        let path = allowlist("empty", "# nobody\n\n");
+        let error = ClaimsPolicy::load(false, Some(&path)).unwrap_err();
+        assert!(error.ends_with("lists no issuers"), "{}", error);
+        assert!(ClaimsPolicy::load(false, Some(Path::new("/nonexistent/allowlist"))).is_err());
+    }
+
+    #[test]
+    fn unsigned_components_pass_without_a_policy() {
+        let mut manifest = ConstructorManifest::new();
+        add(&mut manifest, "app", None);
+        let report = check(&ClaimsPolicy::default(), &manifest);
+        assert!(report.errors.is_empty() && report.warnings.is_empty());
+    }
+
+    #[test]
+    fn signing_policy_rejects_unsigned_invalid_and_expired_components() {
+        let mut manifest = ConstructorManifest::new();
+        add(&mut manifest, "signed", Some(claims(ISSUER)));
+        add(&mut manifest, "unsigned", None);
+        let tampered = ComponentClaims {
+            signature_valid: false,
+            ..claims(ISSUER)
+        };
+        add(&mut manifest, "tampered", Some(tampered));
+        let expired = ComponentClaims {
+            expires: Some(1),
+            ..claims(ISSUER)
+        };
+        add(&mut manifest, "expired", Some(expired));
+        let early = ComponentClaims {
+            not_before: Some(u64::MAX),
+            ..claims(ISSUER)
+        };
+        add(&mut manifest, "early", Some(early));
+
+        let report = check(&ClaimsPolicy::load(true, None).unwrap(), &manifest);
+        let errors = errors(&report);
+        assert_eq!(errors.len(), 4, "{:?}", errors);
+        assert!(errors[0].contains("unsigned is not signed"));
+        assert!(errors[1].contains("tampered has an invalid signature"));
+        assert!(errors[2].contains("The claims of expired are expired"));
+        assert!(errors[3].contains("The claims of early are expired or not valid yet"));
+    }
+
+    #[test]
+    fn allowlist_rejects_other_issuers() {
+        let mut manifest = ConstructorManifest::new();
+        add(&mut manifest, "trusted", Some(claims(ISSUER)));
+        add(&mut manifest, "untrusted", Some(claims("OTHER")));
+
+        let path = allowlist("issuer", ISSUER);
+        let report = check(&ClaimsPolicy::load(false, Some(&path)).unwrap(), &manifest);
+        let errors = errors(&report);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(errors[0]
+            .contains("untrusted is signed by OTHER, which is not in the issuer allowlist"));
+    }
+
+    #[test]
+    fn remote_images_are_warned_about() {
+        let mut manifest = ConstructorManifest::new();
+        manifest
+            .merge_component_info(
+                "remote".to_string(),
+                ComponentInfo {
+                    imports: Vec::new(),
+                    exports: Vec::new(),
+                    package: None,
+                    claims: None,
+                    producers: Default::default(),
+                    registry: None,
+                    version: None,
+                },
+                PathBuf::from("ghcr.io/acme/remote:1.0.0"),
+                EntityKind::Component,
+            )
+            .unwrap();
+
+        let report = check(&ClaimsPolicy::load(true, None).unwrap(), &manifest);
+        assert!(report.errors.is_empty());
+        assert_eq!(report.warnings.len(), 1);
+        assert!(report.warnings[0].starts_with("Cannot verify the claims of remote"));
+    }
+}
@@ -0,0 +1,208 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::models::{InterfaceAlias, InterfaceIdentifier, InterfaceInfo};
+
+/// Interface equivalences from `aliases` in components.yaml and `--alias-rules` files
+#[derive(Debug, Default)]
+pub struct AliasRules {
+    rules: Vec<AliasRule>,
+}
+
+#[derive(Debug)]
+struct AliasRule {
+    name: String,
+    interfaces: Vec<AliasPattern>,
+}
+
+/// `namespace:package/interface`, optionally limited to a version or release line
+#[derive(Debug)]
+struct AliasPattern {
+    id: InterfaceIdentifier,
+    version: Option<String>,
+}
+
+/// An `--alias-rules` file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AliasFile {
+    aliases: Vec<InterfaceAlias>,
+}
+
+impl AliasRules {
+    /// Reads the rules of an `--alias-rules` file
+    pub fn load(path: &Path) -> Result<Vec<InterfaceAlias>, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let file: AliasFile = serde_yaml::from_str(&content)
+            .map_err(|e| format!("failed to parse {}: {}", path.display(), e))?;
+        Ok(file.aliases)
+    }
+
+    pub fn add(&mut self, aliases: &[InterfaceAlias]) -> Result<(), String> {
+        for alias in aliases {
+            let interfaces = alias
+                .interfaces
+                .iter()
+                .map(|interface| {
+                    let id = InterfaceIdentifier::parse(interface).ok_or_else(|| {
+                        format!(
+                            "invalid interface {} in alias rule, expected \
+                             namespace:package/interface",
+                            interface
+                        )
+                    })?;
+                    let version = interface
+                        .split_once('@')
+                        .map(|(_, version)| version.to_string());
+                    Ok(AliasPattern { id, version })
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            if interfaces.len() < 2 {
+                return Err(format!(
+                    "alias rule {} needs at least two interfaces",
+                    alias.interfaces.join(", ")
+                ));
+            }
+            self.rules.push(AliasRule {
+                name: alias
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| alias.interfaces.join(" = ")),
+                interfaces,
+            });
+        }
+        Ok(())
+    }
+
+    /// The name of the first rule making `export` satisfy `import`, through two different
+    /// entries of the rule
+    pub fn rule_for(&self, import: &InterfaceInfo, export: &InterfaceInfo) -> Option<&str> {
+        self.rules
+            .iter()
+            .find(|rule| {
+                rule.interfaces.iter().enumerate().any(|(i, imported)| {
+                    imported.matches(import)
+                        && rule
+                            .interfaces
+                            .iter()
+                            .enumerate()
+                            .any(|(j, exported)| i != j && exported.matches(export))
+                })
+            })
+            .map(|rule| rule.name.as_str())
+    }
+}
+
+impl AliasPattern {
+    /// Interfaces of unknown version, such as those of catalog providers, match any version
+    fn matches(&self, interface: &InterfaceInfo) -> bool {
+        self.id.namespace == interface.namespace
+            && self.id.package == interface.package
+            && self.id.name == interface.name
+            && match (&self.version, &interface.version) {
+                (Some(wanted), Some(version)) => {
+                    version == wanted
+                        || version
+                            .strip_prefix(wanted.as_str())
+                            .is_some_and(|rest| rest.starts_with('.'))
+                }
+                _ => true,
+            }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses `namespace:package/interface[@version]`
+    fn interface(reference: &str) -> InterfaceInfo {
+        let (unversioned, version) = match reference.split_once('@') {
+            Some((unversioned, version)) => (unversioned, Some(version.to_string())),
+            None => (reference, None),
+        };
+        let id = InterfaceIdentifier::parse(unversioned).unwrap();
+        InterfaceInfo {
+            name: id.name,
+            namespace: id.namespace,
+            package: id.package,
+            version,
+            functions: Vec::new(),
+            resources_from: Vec::new(),
+        }
+    }
+
+    fn rules(aliases: &[(Option<&str>, &[&str])]) -> Result<AliasRules, String> {
+        let aliases: Vec<_> = aliases
+            .iter()
+            .map(|(name, interfaces)| InterfaceAlias {
+                name: name.map(str::to_string),
+                interfaces: interfaces.iter().map(|i| i.to_string()).collect(),
+            })
+            .collect();
+        let mut rules = AliasRules::default();
+        rules.add(&aliases)?;
+        Ok(rules)
+    }
+
+    #[test]
+    fn rule_matches_across_entries() {
+        let rules = rules(&[(
+            Some("kv"),
+            &["wasi:keyvalue/store@0.2.0-draft", "acme:kv/store"],
+        )])
+        .unwrap();
+        let draft = interface("wasi:keyvalue/store@0.2.0-draft");
+        let acme = interface("acme:kv/store@1.4.0");
+        assert_eq!(rules.rule_for(&draft, &acme), Some("kv"));
+        assert_eq!(rules.rule_for(&acme, &draft), Some("kv"));
+        // A rule does not make an interface satisfy itself
+        assert_eq!(rules.rule_for(&draft, &draft), None);
+        // Other versions of a pinned interface are not covered
+        let released = interface("wasi:keyvalue/store@0.2.0");
+        assert_eq!(rules.rule_for(&released, &acme), None);
+    }
+
+    #[test]
+    fn partial_version_matches_release_line() {
+        let rules = rules(&[(None, &["acme:kv/store@0.1", "acme:kv/store@0.2"])]).unwrap();
+        let name = "acme:kv/store@0.1 = acme:kv/store@0.2";
+        assert_eq!(
+            rules.rule_for(
+                &interface("acme:kv/store@0.1.3"),
+                &interface("acme:kv/store@0.2.0")
+            ),
+            Some(name)
+        );
+        // `0.1` is a release line, not a prefix of `0.10`
+        assert_eq!(
+            rules.rule_for(
+                &interface("acme:kv/store@0.10.0"),
+                &interface("acme:kv/store@0.2.0")
+            ),
+            None
+        );
+        // Unknown versions match any pattern version
+        assert_eq!(
+            rules.rule_for(
+                &interface("acme:kv/store"),
+                &interface("acme:kv/store@0.2.1")
+            ),
+            Some(name)
+        );
+    }
+
+    #[test]
+    fn invalid_rules() {
+        assert_eq!(
+            rules(&[(None, &["acme:kv/store"])]).unwrap_err(),
+            "alias rule acme:kv/store needs at least two interfaces"
+        );
+        assert_eq!(
+            rules(&[(None, &["acme:kv/store", "acme-kv"])]).unwrap_err(),
+            "invalid interface acme-kv in alias rule, expected namespace:package/interface"
+        );
+    }
+}
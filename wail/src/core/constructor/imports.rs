@@ -0,0 +1,46 @@
+use std::fmt;
+
+use crate::models::InterfaceIdentifier;
+
+use super::LinkConstructor;
+
+/// How an import is treated when no link satisfies it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportPolicy {
+    /// Linked when some component exports it, otherwise left unlinked with a warning
+    Optional,
+    /// Provided outside wadm, e.g. by a host plugin or sidecar, and never linked
+    External,
+    /// On the global ignore list and never linked
+    Ignored,
+}
+
+impl fmt::Display for ImportPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ImportPolicy::Optional => "optional",
+            ImportPolicy::External => "external",
+            ImportPolicy::Ignored => "ignored",
+        })
+    }
+}
+
+/// An import policy for one component's imports, or every component's without one
+#[derive(Debug)]
+pub(super) struct ImportRule {
+    pub component: Option<String>,
+    /// `*` as the name covers the whole package
+    pub interface: InterfaceIdentifier,
+    pub policy: ImportPolicy,
+}
+
+impl ImportRule {
+    pub fn matches(&self, link: &LinkConstructor) -> bool {
+        self.component
+            .as_ref()
+            .is_none_or(|component| *component == link.pre_component_id)
+            && self.interface.namespace == link.namespace
+            && self.interface.package == link.package
+            && (self.interface.name == "*" || self.interface.name == link.interfaces[0])
+    }
+}
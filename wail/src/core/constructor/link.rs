@@ -16,9 +16,25 @@ pub struct LinkConstructor {
     pub namespace: String,
     /// The package for the interfaces.
     pub package: String,
+    /// The wasmCloud link name. `None` is the default link.
+    #[serde(default)]
+    pub link_name: Option<String>,
 }
 
+/// Link name used by wasmCloud when none is given
+pub const DEFAULT_LINK_NAME: &str = "default";
+
 impl LinkConstructor {
+    /// The wasmCloud link name, `default` unless a named link was requested
+    pub fn link_name(&self) -> &str {
+        self.link_name.as_deref().unwrap_or(DEFAULT_LINK_NAME)
+    }
+
+    /// Whether this constructor is for the same link name as `name` (`None` meaning default)
+    pub fn has_link_name(&self, name: Option<&str>) -> bool {
+        self.link_name() == name.unwrap_or(DEFAULT_LINK_NAME)
+    }
+
     pub fn to_wadm_link(&self) -> Trait {
         Trait::new_link(LinkProperty {
             namespace: self.namespace.clone(),
@@ -30,15 +46,36 @@ impl LinkConstructor {
                 config: vec![],
                 secrets: vec![],
             },
-            name: Some(format!(
-                "{}-{}",
-                self.pre_component_id,
-                self.post_component_id.clone().unwrap_or_default()
-            )),
+            name: Some(self.link_name().to_string()),
             ..Default::default()
         })
     }
 
+    /// Converts constructors to link traits, combining the interfaces of constructors that
+    /// share source, target, package and link name into a single trait as wasmCloud expects
+    pub fn to_wadm_links<'a>(links: impl IntoIterator<Item = &'a LinkConstructor>) -> Vec<Trait> {
+        let mut merged: Vec<LinkConstructor> = Vec::new();
+        for link in links {
+            match merged.iter_mut().find(|m| {
+                m.pre_component_id == link.pre_component_id
+                    && m.post_component_id == link.post_component_id
+                    && m.namespace == link.namespace
+                    && m.package == link.package
+                    && m.link_name() == link.link_name()
+            }) {
+                Some(existing) => {
+                    for interface in &link.interfaces {
+                        if !existing.interfaces.contains(interface) {
+                            existing.interfaces.push(interface.clone());
+                        }
+                    }
+                }
+                None => merged.push(link.clone()),
+            }
+        }
+        merged.iter().map(LinkConstructor::to_wadm_link).collect()
+    }
+
     pub fn validate(&self) -> Result<(), String> {
         if self.interfaces.is_empty() {
             return Err("Link must have at least one interface".to_string());
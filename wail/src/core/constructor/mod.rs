@@ -2,14 +2,20 @@ use std::{collections::HashMap, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
 use wadm_types::{
-    Component, ComponentProperties, Manifest, Properties, Specification, TraitProperty,
+    CapabilityProperties, Component, ComponentProperties, ConfigProperty, Manifest, Policy,
+    Properties, SecretProperty, SharedApplicationComponentProperties, Specification,
+    SpreadScalerProperty, Trait, TraitProperty,
 };
 
-use validation::ValidationError;
-
+mod alias;
+mod imports;
 mod link;
 mod validation;
-pub use link::LinkConstructor;
+pub use alias::AliasRules;
+pub use imports::ImportPolicy;
+use imports::ImportRule;
+pub use link::{LinkConstructor, DEFAULT_LINK_NAME};
+pub use validation::{ValidationError, ValidationReport};
 
 /// List of WASI interfaces that are automatically satisfied by the runtime
 pub const RUNTIME_INTERFACES: &[(&str, &str, &str)] = &[
@@ -28,8 +34,11 @@ pub const RUNTIME_INTERFACES: &[(&str, &str, &str)] = &[
 ];
 
 use crate::{
-    core::process_wasm_file,
-    models::{ComponentInfo, InterfaceInfo, PackageInfo},
+    core::{
+        lookup_provider, lookup_provider_by_name, shared_reference, ComponentCache,
+        SharedApplications,
+    },
+    models::{ComponentInfo, EntityKind, InterfaceAlias, InterfaceIdentifier},
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -45,6 +54,40 @@ pub struct ConstructorManifest {
     pub component_interfaces: HashMap<String, ComponentInfo>, // Track ALL interfaces
     #[serde(skip_serializing)]
     pub link_constructors: Vec<LinkConstructor>, // ONLY for imports that need linking
+    /// (input, component name) for every component declaration seen, duplicates included
+    #[serde(skip)]
+    pub declarations: Vec<(String, String)>,
+    /// Interface equivalences used when matching imports to exports
+    #[serde(skip)]
+    pub aliases: AliasRules,
+    /// Imports that may stay unlinked, by component or for every component
+    #[serde(skip)]
+    import_rules: Vec<ImportRule>,
+    /// Every explicit link applied, in order, so later inputs overriding earlier ones can be
+    /// traced
+    #[serde(skip)]
+    pub link_settings: Vec<LinkSetting>,
+    /// Local copies remote components (`url:` and `pkg://` sources) were decoded from, by
+    /// component name
+    #[serde(skip)]
+    pub local_copies: HashMap<String, PathBuf>,
+    /// Links `validate` pointed at the first declared of several exporting components, by
+    /// index into `link_constructors`, with the candidates in the order they were considered
+    #[serde(skip)]
+    pub ambiguous_targets: Vec<(usize, Vec<String>)>,
+}
+
+/// An explicit link for one import, as an input declared it
+#[derive(Debug, Clone)]
+pub struct LinkSetting {
+    pub component: String,
+    pub namespace: String,
+    pub package: String,
+    pub interface: String,
+    pub link_name: Option<String>,
+    pub target: Option<String>,
+    /// The input declaring the link
+    pub input: String,
 }
 
 impl ConstructorManifest {
@@ -60,11 +103,92 @@ impl ConstructorManifest {
             },
             link_constructors: Vec::new(),
             component_interfaces: HashMap::new(),
+            declarations: Vec::new(),
+            aliases: AliasRules::default(),
+            import_rules: Vec::new(),
+            link_settings: Vec::new(),
+            local_copies: HashMap::new(),
+            ambiguous_targets: Vec::new(),
+        }
+    }
+
+    /// The local file `component` was decoded from: its image if that is a local file, or
+    /// the downloaded copy of a remote component
+    pub fn local_file(&self, component: &str) -> Option<PathBuf> {
+        if let Some(path) = self.local_copies.get(component) {
+            return Some(path.clone());
+        }
+        let image = match &self.get_component(component)?.properties {
+            Properties::Component { properties } => properties.image.as_deref(),
+            Properties::Capability { properties } => properties.image.as_deref(),
+        }?;
+        let path = PathBuf::from(image.trim_start_matches("file://"));
+        path.is_file().then_some(path)
+    }
+
+    /// Adds interface alias rules from components.yaml or an `--alias-rules` file
+    pub fn add_aliases(&mut self, aliases: &[InterfaceAlias]) -> Result<(), String> {
+        self.aliases.add(aliases)
+    }
+
+    /// Records that `input` declares `component`, so duplicate declarations can be linted
+    pub fn declare(&mut self, input: &str, component: &str) {
+        self.declarations
+            .push((input.to_string(), component.to_string()));
+    }
+
+    /// Marks `component`'s imports of `interface`, or every component's without a component,
+    /// as optional, external or ignored. `namespace:package/*` covers a whole package.
+    pub fn set_import_policy(
+        &mut self,
+        component: Option<&str>,
+        interface: &str,
+        policy: ImportPolicy,
+    ) -> Result<(), String> {
+        let id = InterfaceIdentifier::parse(interface).ok_or_else(|| {
+            format!(
+                "invalid {} import {}, expected namespace:package/interface",
+                policy, interface
+            )
+        })?;
+        if let Some(info) = component.and_then(|c| self.component_interfaces.get(c)) {
+            if !info.imports.iter().any(|import| {
+                import.namespace == id.namespace
+                    && import.package == id.package
+                    && (id.name == "*" || import.name == id.name)
+            }) {
+                return Err(format!(
+                    "Component {} does not import interface {}",
+                    component.unwrap_or_default(),
+                    interface
+                ));
+            }
         }
+        self.import_rules.push(ImportRule {
+            component: component.map(str::to_string),
+            interface: id,
+            policy,
+        });
+        Ok(())
+    }
+
+    /// The policy for the import `link` binds, preferring the component's own over the
+    /// global ignore list
+    pub fn import_policy(&self, link: &LinkConstructor) -> Option<ImportPolicy> {
+        let rules = || self.import_rules.iter().filter(|rule| rule.matches(link));
+        rules()
+            .find(|rule| rule.component.is_some())
+            .or_else(|| rules().next())
+            .map(|rule| rule.policy)
     }
 
     /// Merges a WADM manifest into this constructor
-    pub fn merge_wadm(&mut self, wadm: &Manifest) -> Result<(), String> {
+    pub fn merge_wadm(
+        &mut self,
+        wadm: &Manifest,
+        cache: &mut ComponentCache,
+        shared: &SharedApplications,
+    ) -> Result<(), String> {
         // Basic metadata merging stays the same
         if self.metadata.is_none() {
             self.metadata = Some(wadm.metadata.clone());
@@ -79,61 +203,33 @@ impl ConstructorManifest {
         // Process each WADM component
         for wadm_component in wadm.components() {
             println!("\nProcessing WADM component: {}", wadm_component.name);
+            self.declare("the WADM manifest", &wadm_component.name);
 
-            if self.component_exists(&wadm_component.name) {
-                // EXISTING COMPONENT: Only validate and apply link configs
-                if let Some(traits) = &wadm_component.traits {
-                    for trait_def in traits {
-                        if trait_def.is_link() {
-                            if let TraitProperty::Link(link) = &trait_def.properties {
-                                // Find matching import in component_interfaces
-                                if let Some(info) =
-                                    self.component_interfaces.get(&wadm_component.name)
-                                {
-                                    // Verify this is actually an import
-                                    if !info.imports.iter().any(|import| {
-                                        import.name == link.interfaces[0]
-                                            && import.namespace == link.namespace
-                                            && import.package == link.package
-                                    }) {
-                                        return Err(format!(
-                                            "Component {} does not import interface {}:{}:{}",
-                                            wadm_component.name,
-                                            link.namespace,
-                                            link.package,
-                                            link.interfaces[0]
-                                        ));
-                                    }
-
-                                    // Find and update matching link constructor
-                                    if let Some(existing_link) =
-                                        self.link_constructors.iter_mut().find(|l| {
-                                            l.pre_component_id == wadm_component.name
-                                                && l.interfaces == link.interfaces
-                                                && l.namespace == link.namespace
-                                                && l.package == link.package
-                                        })
-                                    {
-                                        existing_link.post_component_id =
-                                            Some(link.target.name.clone());
-                                    }
-                                }
-                            }
-                        }
-                    }
+            if let Some(reference) = shared_reference(&wadm_component.properties) {
+                if !self.component_exists(&wadm_component.name) {
+                    let (info, kind) = shared
+                        .resolve(reference, cache)
+                        .map_err(|e| format!("{}: {:#}", wadm_component.name, e))?;
+                    self.merge_shared_component(
+                        wadm_component.name.clone(),
+                        info,
+                        reference.clone(),
+                        kind,
+                    )?;
                 }
-            } else {
+            } else if !self.component_exists(&wadm_component.name) {
                 // NEW COMPONENT: Process WASM and add interfaces
                 match &wadm_component.properties {
                     Properties::Component { properties } => {
                         if let Some(image) = &properties.image {
                             let path = PathBuf::from(image.trim_start_matches("file://"));
-                            match process_wasm_file(&wadm_component.name, &path) {
+                            match cache.process_wasm_file(&wadm_component.name, &path) {
                                 Ok(component_info) => {
                                     self.merge_component_info(
                                         wadm_component.name.clone(),
                                         component_info,
                                         path,
+                                        EntityKind::Component,
                                     )?;
                                 }
                                 Err(e) => eprintln!("Failed to process WASM file: {}", e),
@@ -141,52 +237,260 @@ impl ConstructorManifest {
                         }
                     }
                     Properties::Capability { .. } => {
-                        if wadm_component.name == "httpserver" {
-                            self.process_known_provider(wadm_component)?;
-                        }
+                        self.process_known_provider(wadm_component)?;
+                    }
+                }
+            }
+
+            // Carry over the config and secrets the component is started with
+            let (config, secrets) = match &wadm_component.properties {
+                Properties::Component { properties } => (&properties.config, &properties.secrets),
+                Properties::Capability { properties } => (&properties.config, &properties.secrets),
+            };
+            if self.component_exists(&wadm_component.name)
+                && (!config.is_empty() || !secrets.is_empty())
+            {
+                self.apply_config(&wadm_component.name, config.clone(), secrets.clone())?;
+            }
+
+            // Validate and apply explicit link configs
+            if let Some(traits) = &wadm_component.traits {
+                for trait_def in traits {
+                    if let TraitProperty::Link(link) = &trait_def.properties {
+                        self.apply_link(
+                            "the WADM manifest",
+                            &wadm_component.name,
+                            &link.namespace,
+                            &link.package,
+                            &link.interfaces,
+                            link.name.as_deref(),
+                            Some(link.target.name.clone()),
+                        )?;
                     }
                 }
             }
         }
 
-        self.spec.policies.extend(wadm.policies().cloned());
+        for policy in wadm.policies() {
+            self.add_policy(policy.clone());
+        }
         Ok(())
     }
 
-    fn process_known_provider(&mut self, component: &Component) -> Result<(), String> {
-        match component.name.as_str() {
-            "httpserver" => {
-                let info = ComponentInfo {
-                    imports: vec![InterfaceInfo {
-                        name: "incoming-handler".to_string(),
-                        namespace: "wasi".to_string(),
-                        package: "http".to_string(),
-                    }],
-                    exports: vec![InterfaceInfo {
-                        name: "outgoing-handler".to_string(),
-                        namespace: "wasi".to_string(),
-                        package: "http".to_string(),
-                    }],
-                    package: Some(PackageInfo {
-                        namespace: "wasmcloud".to_string(),
-                        name: "httpserver".to_string(),
-                    }),
-                };
-                match &component.properties {
-                    Properties::Component { properties } => self.merge_component_info(
-                        component.name.clone(),
-                        info,
-                        PathBuf::from(properties.image.as_ref().unwrap_or(&String::new())),
-                    ),
-                    Properties::Capability { properties } => self.merge_component_info(
-                        component.name.clone(),
-                        info,
-                        PathBuf::from(properties.image.as_ref().unwrap_or(&String::new())),
-                    ),
+    /// Applies an explicit link from `input` to the matching imports of `component`.
+    ///
+    /// Links using the default name configure the constructors created from the component's
+    /// imports. Named links get constructors of their own, so one import can be bound to
+    /// several targets, and replace the default link unless it was given a target as well.
+    #[allow(clippy::too_many_arguments)]
+    pub fn apply_link(
+        &mut self,
+        input: &str,
+        component: &str,
+        namespace: &str,
+        package: &str,
+        interfaces: &[String],
+        link_name: Option<&str>,
+        target: Option<String>,
+    ) -> Result<(), String> {
+        // Find matching import in component_interfaces
+        let Some(info) = self.component_interfaces.get(component) else {
+            return Ok(());
+        };
+        let link_name = link_name.filter(|name| *name != DEFAULT_LINK_NAME);
+
+        for interface in interfaces {
+            // Verify this is actually an import
+            if !info.imports.iter().any(|import| {
+                &import.name == interface
+                    && import.namespace == namespace
+                    && import.package == package
+            }) {
+                return Err(format!(
+                    "Component {} does not import interface {}:{}:{}",
+                    component, namespace, package, interface
+                ));
+            }
+            self.link_settings.push(LinkSetting {
+                component: component.to_string(),
+                namespace: namespace.to_string(),
+                package: package.to_string(),
+                interface: interface.clone(),
+                link_name: link_name.map(str::to_string),
+                target: target.clone(),
+                input: input.to_string(),
+            });
+
+            // Find and update matching link constructor
+            match self.link_constructors.iter_mut().find(|l| {
+                l.pre_component_id == component
+                    && l.interfaces.len() == 1
+                    && &l.interfaces[0] == interface
+                    && l.namespace == namespace
+                    && l.package == package
+                    && l.has_link_name(link_name)
+            }) {
+                Some(existing_link) => {
+                    if target.is_some() {
+                        existing_link.post_component_id = target.clone();
+                    }
+                }
+                None => {
+                    println!(
+                        "  Adding link '{}' for {}: {}:{}:{}",
+                        link_name.unwrap_or(DEFAULT_LINK_NAME),
+                        component,
+                        namespace,
+                        package,
+                        interface
+                    );
+                    self.link_constructors.push(LinkConstructor {
+                        pre_component_id: component.to_string(),
+                        post_component_id: target.clone(),
+                        interfaces: vec![interface.clone()],
+                        namespace: namespace.to_string(),
+                        package: package.to_string(),
+                        link_name: link_name.map(str::to_string),
+                    });
                 }
             }
-            _ => Ok(()),
+
+            // A named link takes over the import unless the default link was configured too
+            if link_name.is_some() {
+                self.link_constructors.retain(|l| {
+                    !(l.pre_component_id == component
+                        && l.interfaces.len() == 1
+                        && &l.interfaces[0] == interface
+                        && l.namespace == namespace
+                        && l.package == package
+                        && l.link_name.is_none()
+                        && l.post_component_id.is_none())
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sets the config and secrets `component` is started with, replacing any set before
+    pub fn apply_config(
+        &mut self,
+        component: &str,
+        config: Vec<ConfigProperty>,
+        secrets: Vec<SecretProperty>,
+    ) -> Result<(), String> {
+        let Some(target) = self
+            .spec
+            .components
+            .iter_mut()
+            .find(|c| c.name == component)
+        else {
+            return Err(format!("Component {} not found", component));
+        };
+        match &mut target.properties {
+            Properties::Component { properties } => {
+                properties.config = config;
+                properties.secrets = secrets;
+            }
+            Properties::Capability { properties } => {
+                properties.config = config;
+                properties.secrets = secrets;
+            }
+        }
+        Ok(())
+    }
+
+    /// Scales `component` to `instances` with a spreadscaler trait, replacing any existing one
+    pub fn set_instances(&mut self, component: &str, instances: usize) -> Result<(), String> {
+        let Some(target) = self
+            .spec
+            .components
+            .iter_mut()
+            .find(|c| c.name == component)
+        else {
+            return Err(format!("Component {} not found", component));
+        };
+        let traits = target.traits.get_or_insert_with(Vec::new);
+        traits.retain(|t| !matches!(t.properties, TraitProperty::SpreadScaler(_)));
+        traits.push(Trait::new_spreadscaler(SpreadScalerProperty {
+            instances,
+            spread: Vec::new(),
+        }));
+        Ok(())
+    }
+
+    /// Adds a policy, replacing an existing policy of the same name
+    pub fn add_policy(&mut self, policy: Policy) {
+        match self
+            .spec
+            .policies
+            .iter_mut()
+            .find(|p| p.name == policy.name)
+        {
+            Some(existing) => *existing = policy,
+            None => self.spec.policies.push(policy),
+        }
+    }
+
+    fn process_known_provider(&mut self, component: &Component) -> Result<(), String> {
+        let image = match &component.properties {
+            Properties::Component { properties } => properties.image.clone(),
+            Properties::Capability { properties } => properties.image.clone(),
+        }
+        .unwrap_or_default();
+
+        let Some(provider) =
+            lookup_provider_by_name(&component.name).or_else(|| lookup_provider(&image))
+        else {
+            println!(
+                "No interface information known for capability {}",
+                component.name
+            );
+            return Ok(());
+        };
+
+        self.merge_component_info(
+            component.name.clone(),
+            provider.component_info(),
+            PathBuf::from(image),
+            EntityKind::Capability,
+        )
+    }
+
+    /// Adds a reference to a component deployed by a shared application. It is emitted with
+    /// `application:` instead of an image and only contributes its exports as link targets.
+    pub fn merge_shared_component(
+        &mut self,
+        name: String,
+        info: ComponentInfo,
+        reference: SharedApplicationComponentProperties,
+        kind: EntityKind,
+    ) -> Result<(), String> {
+        println!(
+            "Referencing {} from shared application {} as {}",
+            reference.component, reference.name, name
+        );
+        self.merge_component_info(name.clone(), info, PathBuf::new(), kind)?;
+
+        if let Some(component) = self.spec.components.iter_mut().find(|c| c.name == name) {
+            // The shared application owns the component's image and id
+            let (image, id, application) = match &mut component.properties {
+                Properties::Component { properties } => (
+                    &mut properties.image,
+                    &mut properties.id,
+                    &mut properties.application,
+                ),
+                Properties::Capability { properties } => (
+                    &mut properties.image,
+                    &mut properties.id,
+                    &mut properties.application,
+                ),
+            };
+            *image = None;
+            *id = None;
+            *application = Some(reference);
         }
+        Ok(())
     }
 
     pub fn merge_component_info(
@@ -194,30 +498,46 @@ impl ConstructorManifest {
         name: String,
         info: ComponentInfo,
         file_path: PathBuf,
+        kind: EntityKind,
     ) -> Result<(), String> {
         // Store ALL interface information
         self.component_interfaces.insert(name.clone(), info.clone());
 
+        let image = Some(file_path.to_string_lossy().to_string());
+
         // Add/update the component
         match self.spec.components.iter().position(|c| c.name == name) {
             Some(index) => {
                 let component = &mut self.spec.components[index];
-                if let Properties::Component { properties } = &mut component.properties {
-                    properties.image = Some(file_path.to_string_lossy().to_string());
+                match &mut component.properties {
+                    Properties::Component { properties } => properties.image = image,
+                    Properties::Capability { properties } => properties.image = image,
                 }
             }
             None => {
-                self.spec.components.push(Component {
-                    name: name.clone(),
-                    properties: Properties::Component {
+                let properties = match kind {
+                    EntityKind::Component => Properties::Component {
                         properties: ComponentProperties {
-                            image: Some(file_path.to_string_lossy().to_string()),
+                            image,
+                            application: None,
+                            id: Some(name.clone()),
+                            config: Vec::new(),
+                            secrets: Vec::new(),
+                        },
+                    },
+                    EntityKind::Capability => Properties::Capability {
+                        properties: CapabilityProperties {
+                            image,
                             application: None,
                             id: Some(name.clone()),
                             config: Vec::new(),
                             secrets: Vec::new(),
                         },
                     },
+                };
+                self.spec.components.push(Component {
+                    name: name.clone(),
+                    properties,
                     traits: Some(Vec::new()),
                 });
             }
@@ -252,6 +572,7 @@ impl ConstructorManifest {
                 interfaces: vec![import.name.clone()],
                 namespace: import.namespace.clone(),
                 package: import.package.clone(),
+                link_name: None,
             });
         }
 
@@ -294,9 +615,7 @@ impl ConstructorManifest {
                 }
 
                 if let Some(traits) = &mut comp.traits {
-                    for link in component_links {
-                        traits.push(link.to_wadm_link());
-                    }
+                    traits.extend(LinkConstructor::to_wadm_links(component_links));
                 }
             }
 
@@ -1,7 +1,11 @@
-use crate::models::InterfaceRequirement;
+use std::collections::{HashMap, HashSet};
 
-use super::{ConstructorManifest, LinkConstructor, RUNTIME_INTERFACES};
-use wadm_types::{Component, Properties};
+use semver::Version;
+
+use crate::models::{Direction, InterfaceIdentifier, InterfaceInfo, InterfaceRequirement};
+
+use super::{ConstructorManifest, ImportPolicy, LinkConstructor, RUNTIME_INTERFACES};
+use wadm_types::{Component, Properties, SECRET_POLICY_TYPE};
 
 #[derive(Debug)]
 pub struct UnlinkedInterface {
@@ -18,16 +22,44 @@ pub enum ValidationError {
     LinkError(String),
     #[error("Interface error: {0}")]
     InterfaceError(String),
+    #[error("Lint error [{rule}]: {message}")]
+    LintError { rule: &'static str, message: String },
     // #[error("Resolver error: {0}")]
     // ResolverError(#[from] ResolverError),
 }
 
+/// A link whose target exports an interface an alias rule makes equivalent to the import
+#[derive(Debug)]
+pub struct AliasedLink {
+    pub link: LinkConstructor,
+    /// The source's interface, as `namespace:package/interface@version`
+    pub import: String,
+    /// The target's interface, as `namespace:package/interface@version`
+    pub export: String,
+    /// Name of the alias rule
+    pub rule: String,
+}
+
+/// An import left unlinked because it is optional, external or ignored
+#[derive(Debug)]
+pub struct ExemptImport {
+    pub component: String,
+    /// The import, as `namespace:package/interface`
+    pub interface: String,
+    pub policy: ImportPolicy,
+}
+
 #[derive(Debug, Default)]
 pub struct ValidationReport {
     pub discovered_links: Vec<LinkConstructor>,
+    pub aliased_links: Vec<AliasedLink>,
     pub unlinked_interfaces: Vec<UnlinkedInterface>,
+    /// Imports satisfied outside the manifest or allowed to stay unlinked
+    pub exempt_imports: Vec<ExemptImport>,
     pub warnings: Vec<String>,
     pub errors: Vec<ValidationError>,
+    /// The link each error about a single link concerns, by index into `errors`
+    pub link_errors: Vec<(usize, LinkConstructor)>,
     pub is_valid: bool,
 }
 
@@ -43,11 +75,25 @@ impl ValidationReport {
         self.warnings.push(warning);
     }
 
+    fn exempt(&mut self, link: &LinkConstructor, policy: ImportPolicy) {
+        self.exempt_imports.push(ExemptImport {
+            component: link.pre_component_id.clone(),
+            interface: format!("{}:{}/{}", link.namespace, link.package, link.interfaces[0]),
+            policy,
+        });
+    }
+
     pub fn add_error(&mut self, error: ValidationError) {
         self.is_valid = false;
         self.errors.push(error);
     }
 
+    /// Adds an error about `link`, so it can be traced back to the import it binds
+    pub fn add_link_error(&mut self, link: &LinkConstructor, error: ValidationError) {
+        self.link_errors.push((self.errors.len(), link.clone()));
+        self.add_error(error);
+    }
+
     /// Returns a summary of the validation results
     pub fn summary(&self) -> String {
         let mut summary = Vec::new();
@@ -59,6 +105,20 @@ impl ValidationReport {
             ));
         }
 
+        if !self.aliased_links.is_empty() {
+            summary.push(format!(
+                "{} links through interface aliases",
+                self.aliased_links.len()
+            ));
+        }
+
+        if !self.exempt_imports.is_empty() {
+            summary.push(format!(
+                "{} optional or external imports left unlinked",
+                self.exempt_imports.len()
+            ));
+        }
+
         if !self.unlinked_interfaces.is_empty() {
             summary.push(format!(
                 "Found {} unlinked interfaces",
@@ -87,10 +147,18 @@ impl ConstructorManifest {
     pub fn validate(&mut self) -> Result<ValidationReport, ValidationError> {
         let mut report = ValidationReport::new();
         self.validate_basic_requirements(&mut report)?;
+        // Targets configured before saturation, which resource companions must not move
+        let explicit: Vec<bool> = self
+            .link_constructors
+            .iter()
+            .map(|link| link.post_component_id.is_some())
+            .collect();
+        // Saturated links with several candidate exporters, by index, with the candidates
+        let mut ambiguous: Vec<(usize, Vec<String>)> = Vec::new();
 
         // Check each link constructor (which represents an import that needs satisfying)
-        for link in &mut self.link_constructors {
-            // Note: made mutable
+        for index in 0..self.link_constructors.len() {
+            let link = self.link_constructors[index].clone();
             // Skip WASI runtime interfaces
             if RUNTIME_INTERFACES.iter().any(|(ns, pkg, name)| {
                 ns == &link.namespace && pkg == &link.package && name == &link.interfaces[0]
@@ -102,62 +170,436 @@ impl ConstructorManifest {
                 continue;
             }
 
+            // Imports provided outside wadm are skipped the same way, unless linked explicitly
+            let policy = self.import_policy(&link);
+            if let (None, Some(skipped @ (ImportPolicy::External | ImportPolicy::Ignored))) =
+                (&link.post_component_id, policy)
+            {
+                println!(
+                    "Skipping {} interface {}:{}:{} for {}",
+                    skipped,
+                    link.namespace,
+                    link.package,
+                    link.interfaces[0],
+                    link.pre_component_id
+                );
+                report.exempt(&link, skipped);
+                continue;
+            }
+
             // If it has a target, validate the target exists and exports the interface
             if let Some(target) = &link.post_component_id {
+                // Capability targets are checked by the capability-export lint, since the
+                // provider catalog does not know every provider's interfaces
+                if self.spec.components.iter().any(|c| {
+                    &c.name == target && matches!(c.properties, Properties::Capability { .. })
+                }) {
+                    continue;
+                }
+
                 // Explicit target specified - must use this one
-                if let Some(target_info) = self.component_interfaces.get(target) {
+                if self.component_interfaces.contains_key(target) {
                     // Check target exports this interface
-                    if !target_info.exports.iter().any(|export| {
-                        export.name == link.interfaces[0]
-                            && export.namespace == link.namespace
-                            && export.package == link.package
-                    }) {
-                        report.add_error(ValidationError::InterfaceError(format!(
-                            "Component {} does not export interface {}:{}:{} required by {}",
+                    if self.matching_export(target, &link).is_none() {
+                        report.add_link_error(
+                            &link,
+                            ValidationError::InterfaceError(format!(
+                            "Component {} does not export interface {}:{}:{}{} required by {}{}",
                             target,
                             link.namespace,
                             link.package,
                             link.interfaces[0],
-                            link.pre_component_id
-                        )));
+                            self.import_version(&link)
+                                .map(|v| format!("@{}", v))
+                                .unwrap_or_default(),
+                            link.pre_component_id,
+                            self.component_interfaces
+                                .get(target)
+                                .and_then(|info| find_interface(&info.exports, &link))
+                                .and_then(|export| export.version.as_deref())
+                                .map(|v| format!(" (it exports incompatible version {})", v))
+                                .unwrap_or_default()
+                        )),
+                        );
                     }
                 } else {
-                    report.add_error(ValidationError::ComponentError(format!(
-                        "Target component {} not found",
-                        target
-                    )));
+                    report.add_link_error(
+                        &link,
+                        ValidationError::ComponentError(format!(
+                            "Target component {} not found",
+                            target
+                        )),
+                    );
                 }
             } else {
-                // No target specified - find first matching component
-                let mut found_match = false;
-                for (comp_name, comp_info) in &self.component_interfaces {
-                    if comp_name != &link.pre_component_id {
-                        // Don't match with self
-                        if comp_info.exports.iter().any(|export| {
-                            export.name == link.interfaces[0]
-                                && export.namespace == link.namespace
-                                && export.package == link.package
-                        }) {
-                            // Found a match - update the link constructor with the target
-                            link.post_component_id = Some(comp_name.clone());
-                            println!("Saturated link: {} -> {}", link.pre_component_id, comp_name);
-                            found_match = true;
-                            break; // Take first match
-                        }
-                    }
+                // No target specified - find the first matching component in manifest order,
+                // other than itself, so the choice is the same on every run
+                let mut candidates: Vec<_> = self
+                    .spec
+                    .components
+                    .iter()
+                    .map(|component| &component.name)
+                    .filter(|name| **name != link.pre_component_id)
+                    .filter_map(|name| Some((name, self.matching_export(name, &link)?.1)))
+                    .collect();
+                // Exporters of the interface itself win over ones matched by an alias rule
+                candidates.sort_by_key(|(_, rule)| rule.is_some());
+                if candidates.len() > 1 {
+                    let names = candidates.iter().map(|(name, _)| (*name).clone()).collect();
+                    ambiguous.push((index, names));
                 }
-                if !found_match {
-                    report.add_error(ValidationError::InterfaceError(format!(
-                        "No component found that exports interface {}:{}:{} required by {}",
+                let found = candidates.first().map(|(name, _)| (*name).clone());
+                if let Some(comp_name) = found {
+                    // Found a match - update the link constructor with the target
+                    println!("Saturated link: {} -> {}", link.pre_component_id, comp_name);
+                    self.link_constructors[index].post_component_id = Some(comp_name);
+                } else if policy == Some(ImportPolicy::Optional) {
+                    report.add_warning(format!(
+                        "No component exports optional interface {}:{}/{} of {}, leaving it \
+                         unlinked",
                         link.namespace, link.package, link.interfaces[0], link.pre_component_id
-                    )));
+                    ));
+                    report.exempt(&link, ImportPolicy::Optional);
+                } else {
+                    report.unlinked_interfaces.push(UnlinkedInterface {
+                        component: link.pre_component_id.clone(),
+                        interface: InterfaceRequirement::new(
+                            link.interfaces[0].clone(),
+                            link.namespace.clone(),
+                            link.package.clone(),
+                            Direction::Import,
+                            link.pre_component_id.clone(),
+                        ),
+                        potential_matches: Vec::new(),
+                    });
+                    report.add_link_error(
+                        &link,
+                        ValidationError::InterfaceError(format!(
+                            "No component found that exports interface {}:{}:{}{} required by {}",
+                            link.namespace,
+                            link.package,
+                            link.interfaces[0],
+                            self.import_version(&link)
+                                .map(|v| format!("@{}", v))
+                                .unwrap_or_default(),
+                            link.pre_component_id
+                        )),
+                    );
                 }
             }
         }
 
+        self.link_resource_companions(&explicit, &mut report);
+        // Links moved to follow the import using their resources were not picked by order
+        self.ambiguous_targets = ambiguous
+            .into_iter()
+            .filter(|(index, names)| {
+                self.link_constructors[*index].post_component_id.as_ref() == names.first()
+            })
+            .collect();
+        self.validate_link_names(&mut report);
+        self.validate_structure(&mut report);
+        self.record_aliased_links(&mut report);
+
         Ok(report)
     }
 
+    /// The export of `target` satisfying `link`'s import: the interface itself, else one an
+    /// alias rule makes equivalent, returned with the rule's name
+    pub fn matching_export(
+        &self,
+        target: &str,
+        link: &LinkConstructor,
+    ) -> Option<(&InterfaceInfo, Option<&str>)> {
+        let exports = &self.component_interfaces.get(target)?.exports;
+        let import = self
+            .component_interfaces
+            .get(&link.pre_component_id)
+            .and_then(|info| find_interface(&info.imports, link))
+            .cloned()
+            .unwrap_or_else(|| InterfaceInfo {
+                name: link.interfaces[0].clone(),
+                namespace: link.namespace.clone(),
+                package: link.package.clone(),
+                version: None,
+                functions: Vec::new(),
+                resources_from: Vec::new(),
+            });
+        let direct = find_interface(exports, link).filter(|export| {
+            versions_compatible(import.version.as_deref(), export.version.as_deref())
+        });
+        if let Some(export) = direct {
+            return Some((export, None));
+        }
+        exports
+            .iter()
+            .find_map(|export| Some((export, Some(self.aliases.rule_for(&import, export)?))))
+    }
+
+    /// The version of the interface `link`'s source imports, if known
+    fn import_version(&self, link: &LinkConstructor) -> Option<&str> {
+        let info = self.component_interfaces.get(&link.pre_component_id)?;
+        find_interface(&info.imports, link)?.version.as_deref()
+    }
+
+    /// Lists the links whose target exports an alias of the imported interface
+    fn record_aliased_links(&self, report: &mut ValidationReport) {
+        for link in &self.link_constructors {
+            let Some(target) = link.post_component_id.as_deref() else {
+                continue;
+            };
+            if let Some((export, Some(rule))) = self.matching_export(target, link) {
+                let versioned =
+                    |version: Option<&str>| version.map(|v| format!("@{}", v)).unwrap_or_default();
+                report.aliased_links.push(AliasedLink {
+                    link: link.clone(),
+                    import: format!(
+                        "{}:{}/{}{}",
+                        link.namespace,
+                        link.package,
+                        link.interfaces[0],
+                        versioned(self.import_version(link))
+                    ),
+                    export: format!(
+                        "{}:{}/{}{}",
+                        export.namespace,
+                        export.package,
+                        export.name,
+                        versioned(export.version.as_deref())
+                    ),
+                    rule: rule.to_string(),
+                });
+            }
+        }
+    }
+
+    /// A handle is only valid with the component that created it, so an interface using
+    /// resources defined by another interface (e.g. a store returning `types.bucket`) must be
+    /// linked to the same target as that interface. Saturated companion links are moved to
+    /// the target of the interface using them, missing ones are added, and explicit links
+    /// to different targets are reported.
+    fn link_resource_companions(&mut self, explicit: &[bool], report: &mut ValidationReport) {
+        let is_explicit = |index: usize| explicit.get(index).copied().unwrap_or(false);
+        for index in 0..self.link_constructors.len() {
+            let link = self.link_constructors[index].clone();
+            let Some(target) = link.post_component_id.clone() else {
+                continue;
+            };
+            let Some(origins) = self
+                .component_interfaces
+                .get(&link.pre_component_id)
+                .and_then(|info| find_interface(&info.imports, &link))
+                .map(|import| import.resources_from.clone())
+            else {
+                continue;
+            };
+            let companions: Vec<_> = origins
+                .iter()
+                .filter_map(|origin| InterfaceIdentifier::parse(origin))
+                .filter(|id| {
+                    !RUNTIME_INTERFACES.iter().any(|(ns, pkg, name)| {
+                        ns == &id.namespace && pkg == &id.package && name == &id.name
+                    })
+                })
+                .map(|id| (self.companion_link(&link, &id), id))
+                .collect();
+
+            // A saturated link follows an explicitly linked companion instead
+            let mut target = target;
+            if !is_explicit(index) {
+                let explicit_target = companions.iter().find_map(|(companion, _)| {
+                    let companion = (*companion).filter(|&c| is_explicit(c))?;
+                    self.link_constructors[companion].post_component_id.clone()
+                });
+                if let Some(explicit_target) = explicit_target {
+                    if explicit_target != target && self.exports(&explicit_target, &link) {
+                        println!(
+                            "Moved link {} -> {} for {}:{}/{} to {} with its resources",
+                            link.pre_component_id,
+                            target,
+                            link.namespace,
+                            link.package,
+                            link.interfaces[0],
+                            explicit_target
+                        );
+                        self.link_constructors[index].post_component_id =
+                            Some(explicit_target.clone());
+                        target = explicit_target;
+                    }
+                }
+            }
+
+            for (companion, origin) in companions {
+                let companion_target =
+                    companion.and_then(|c| self.link_constructors[c].post_component_id.as_deref());
+                if companion_target == Some(target.as_str()) {
+                    continue;
+                }
+                let companion_link = LinkConstructor {
+                    pre_component_id: link.pre_component_id.clone(),
+                    post_component_id: Some(target.clone()),
+                    interfaces: vec![origin.name.clone()],
+                    namespace: origin.namespace.clone(),
+                    package: origin.package.clone(),
+                    link_name: link.link_name.clone(),
+                };
+                let movable = companion.is_none_or(|c| !is_explicit(c));
+                if !movable || !self.exports(&target, &companion_link) {
+                    let problem = match companion_target {
+                        Some(other) if !movable => {
+                            format!("which it links to {}", other)
+                        }
+                        _ => format!("which {} does not export", target),
+                    };
+                    report.add_link_error(
+                        &link,
+                        ValidationError::LinkError(format!(
+                        "Component {} links {}:{}/{} to {}, but its resources come from {}, {}; \
+                         both have to be linked to the same target",
+                        link.pre_component_id,
+                        link.namespace,
+                        link.package,
+                        link.interfaces[0],
+                        target,
+                        origin,
+                        problem
+                    )),
+                    );
+                    continue;
+                }
+
+                match companion {
+                    Some(c) => self.link_constructors[c].post_component_id = Some(target.clone()),
+                    None => self.link_constructors.push(companion_link.clone()),
+                }
+                println!(
+                    "Linked {} -> {} for {}, whose resources {}:{}/{} uses",
+                    link.pre_component_id,
+                    target,
+                    origin,
+                    link.namespace,
+                    link.package,
+                    link.interfaces[0]
+                );
+                report.discovered_links.push(companion_link);
+            }
+        }
+    }
+
+    /// The index of the constructor linking `link`'s component to `interface` under the
+    /// same link name
+    fn companion_link(
+        &self,
+        link: &LinkConstructor,
+        interface: &InterfaceIdentifier,
+    ) -> Option<usize> {
+        self.link_constructors.iter().position(|l| {
+            l.pre_component_id == link.pre_component_id
+                && l.namespace == interface.namespace
+                && l.package == interface.package
+                && l.interfaces[0] == interface.name
+                && l.link_name() == link.link_name()
+        })
+    }
+
+    /// Whether `component` exports the interface `link` imports. Capability providers are
+    /// assumed to, the capability-export lint checks them against the provider catalog.
+    fn exports(&self, component: &str, link: &LinkConstructor) -> bool {
+        if self
+            .spec
+            .components
+            .iter()
+            .any(|c| c.name == component && matches!(c.properties, Properties::Capability { .. }))
+        {
+            return true;
+        }
+        self.matching_export(component, link).is_some()
+    }
+
+    /// With `--structural`, every function a component imports through a link must be
+    /// exported by the target. Interfaces without recorded functions (scanned components and
+    /// capability providers) are not compared.
+    fn validate_structure(&self, report: &mut ValidationReport) {
+        for link in &self.link_constructors {
+            let Some(target) = link.post_component_id.as_deref() else {
+                continue;
+            };
+            let Some(importer) = self.component_interfaces.get(&link.pre_component_id) else {
+                continue;
+            };
+            let (Some(import), Some((export, _))) = (
+                find_interface(&importer.imports, link),
+                self.matching_export(target, link),
+            ) else {
+                continue;
+            };
+            if import.functions.is_empty() || export.functions.is_empty() {
+                continue;
+            }
+
+            let missing: Vec<_> = import
+                .functions
+                .iter()
+                .filter(|f| !export.functions.contains(f))
+                .map(String::as_str)
+                .collect();
+            if !missing.is_empty() {
+                report.add_link_error(
+                    link,
+                    ValidationError::InterfaceError(format!(
+                        "Component {} imports {} from {}:{}/{} but {} does not export {}",
+                        link.pre_component_id,
+                        missing.join(", "),
+                        link.namespace,
+                        link.package,
+                        link.interfaces[0],
+                        target,
+                        if missing.len() == 1 { "it" } else { "them" }
+                    )),
+                );
+            }
+        }
+    }
+
+    /// wasmCloud keys links by source, package and link name, so one link name cannot
+    /// point the same package at two different targets
+    fn validate_link_names(&self, report: &mut ValidationReport) {
+        let mut targets: HashMap<(&str, &str, &str, &str), &str> = HashMap::new();
+        let mut conflicts = HashSet::new();
+        for link in &self.link_constructors {
+            let Some(target) = link.post_component_id.as_deref() else {
+                continue;
+            };
+            let key = (
+                link.pre_component_id.as_str(),
+                link.namespace.as_str(),
+                link.package.as_str(),
+                link.link_name(),
+            );
+            match targets.get(&key) {
+                Some(existing) if *existing != target && conflicts.insert((key, target)) => {
+                    report.add_link_error(
+                        link,
+                        ValidationError::LinkError(format!(
+                            "Component {} links {}:{} to both {} and {} under link name '{}'; \
+                         give one of them a different link name",
+                            link.pre_component_id,
+                            link.namespace,
+                            link.package,
+                            existing,
+                            target,
+                            link.link_name()
+                        )),
+                    );
+                }
+                Some(_) => {}
+                None => {
+                    targets.insert(key, target);
+                }
+            }
+        }
+    }
+
     fn validate_basic_requirements(
         &self,
         report: &mut ValidationReport,
@@ -169,16 +611,43 @@ impl ConstructorManifest {
             }
         }
 
+        self.validate_secret_policies(report);
+
         // Validate link references
         for link in &self.link_constructors {
             if let Err(e) = self.validate_link_references(link) {
-                report.add_error(e);
+                report.add_link_error(link, e);
             }
         }
 
         Ok(())
     }
 
+    /// Every secret must name a secret policy defined in the manifest
+    fn validate_secret_policies(&self, report: &mut ValidationReport) {
+        for component in &self.spec.components {
+            let secrets = match &component.properties {
+                Properties::Component { properties } => &properties.secrets,
+                Properties::Capability { properties } => &properties.secrets,
+            };
+            for secret in secrets {
+                let policy = &secret.properties.policy;
+                if !self
+                    .spec
+                    .policies
+                    .iter()
+                    .any(|p| &p.name == policy && p.policy_type == SECRET_POLICY_TYPE)
+                {
+                    report.add_error(ValidationError::ComponentError(format!(
+                        "Secret {} of {} uses backend {} but no secret policy named {} is \
+                         defined; declare it under secret_backends",
+                        secret.name, component.name, policy, policy
+                    )));
+                }
+            }
+        }
+    }
+
     fn validate_component_properties(&self, component: &Component) -> Result<(), ValidationError> {
         let missing_requirements = match &component.properties {
             Properties::Component { properties } => {
@@ -218,3 +687,238 @@ impl ConstructorManifest {
         link.validate().map_err(ValidationError::LinkError)
     }
 }
+
+/// The entry of `interfaces` for the interface `link` binds
+fn find_interface<'a>(
+    interfaces: &'a [InterfaceInfo],
+    link: &LinkConstructor,
+) -> Option<&'a InterfaceInfo> {
+    interfaces.iter().find(|i| {
+        i.namespace == link.namespace && i.package == link.package && i.name == link.interfaces[0]
+    })
+}
+
+/// Whether an export of version `export` satisfies an import of version `import`: both on the
+/// same release line (major version, or minor version below 1.0) and the export at least as
+/// new. Pre-releases only satisfy the same pre-release, and unknown versions satisfy any.
+fn versions_compatible(import: Option<&str>, export: Option<&str>) -> bool {
+    let (Some(import), Some(export)) = (import, export) else {
+        return true;
+    };
+    let (Ok(import), Ok(export)) = (Version::parse(import), Version::parse(export)) else {
+        return import == export;
+    };
+    if !import.pre.is_empty() || !export.pre.is_empty() {
+        return import == export;
+    }
+    let line = |version: &Version| match (version.major, version.minor) {
+        (0, 0) => (0, 0, version.patch),
+        (0, minor) => (0, minor, 0),
+        (major, _) => (major, 0, 0),
+    };
+    line(&import) == line(&export) && export >= import
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::core::{run_lints, LintConfig};
+    use crate::models::{ComponentInfo, EntityKind};
+
+    /// `namespace:package/interface`
+    fn interface(reference: &str) -> InterfaceInfo {
+        let id = InterfaceIdentifier::parse(reference).unwrap();
+        InterfaceInfo {
+            name: id.name,
+            namespace: id.namespace,
+            package: id.package,
+            version: None,
+            functions: Vec::new(),
+            resources_from: Vec::new(),
+        }
+    }
+
+    fn add(manifest: &mut ConstructorManifest, name: &str, imports: &[&str], exports: &[&str]) {
+        let info = ComponentInfo {
+            imports: imports.iter().map(|i| interface(i)).collect(),
+            exports: exports.iter().map(|e| interface(e)).collect(),
+            package: None,
+            claims: None,
+            producers: Default::default(),
+            registry: None,
+            version: None,
+        };
+        manifest
+            .merge_component_info(
+                name.to_string(),
+                info,
+                PathBuf::from(format!("{}.wasm", name)),
+                EntityKind::Component,
+            )
+            .unwrap();
+    }
+
+    fn exempt(report: &ValidationReport) -> Vec<(&str, &str, ImportPolicy)> {
+        report
+            .exempt_imports
+            .iter()
+            .map(|e| (e.component.as_str(), e.interface.as_str(), e.policy))
+            .collect()
+    }
+
+    #[test]
+    fn external_and_ignored_imports_stay_unlinked() {
+        let mut manifest = ConstructorManifest::new();
+        add(
+            &mut manifest,
+            "app",
+            &["acme:kv/store", "acme:log/logging", "acme:metrics/counter"],
+            &[],
+        );
+        manifest
+            .set_import_policy(Some("app"), "acme:kv/store", ImportPolicy::External)
+            .unwrap();
+        manifest
+            .set_import_policy(None, "acme:log/*", ImportPolicy::Ignored)
+            .unwrap();
+
+        let report = manifest.validate().unwrap();
+        assert_eq!(
+            exempt(&report),
+            [
+                ("app", "acme:kv/store", ImportPolicy::External),
+                ("app", "acme:log/logging", ImportPolicy::Ignored),
+            ]
+        );
+        assert_eq!(report.unlinked_interfaces.len(), 1);
+        assert_eq!(
+            report.unlinked_interfaces[0].interface.identifier.name,
+            "counter"
+        );
+        assert!(!report.is_valid);
+    }
+
+    #[test]
+    fn optional_imports_link_when_exported() {
+        let mut manifest = ConstructorManifest::new();
+        add(
+            &mut manifest,
+            "app",
+            &["acme:kv/store", "acme:cache/get"],
+            &[],
+        );
+        add(&mut manifest, "kv", &[], &["acme:kv/store"]);
+        for import in ["acme:kv/store", "acme:cache/get"] {
+            manifest
+                .set_import_policy(Some("app"), import, ImportPolicy::Optional)
+                .unwrap();
+        }
+
+        let report = manifest.validate().unwrap();
+        assert!(report.is_valid);
+        assert_eq!(
+            manifest.link_constructors[0].post_component_id.as_deref(),
+            Some("kv")
+        );
+        assert_eq!(
+            exempt(&report),
+            [("app", "acme:cache/get", ImportPolicy::Optional)]
+        );
+        assert!(report.warnings[0].starts_with("No component exports optional interface"));
+    }
+
+    #[test]
+    fn ambiguous_targets_are_linted() {
+        let mut manifest = ConstructorManifest::new();
+        add(&mut manifest, "app", &["acme:kv/store"], &[]);
+        add(&mut manifest, "redis", &[], &["acme:kv/store"]);
+        add(&mut manifest, "nats", &[], &["acme:kv/store"]);
+
+        let report = manifest.validate().unwrap();
+        assert!(report.warnings.is_empty());
+        assert_eq!(
+            manifest.ambiguous_targets,
+            [(0, vec!["redis".to_string(), "nats".to_string()])]
+        );
+
+        // Findings of the rule at `severity`, as (is error, message)
+        let lint = |severity: &str| {
+            let mut report = ValidationReport::new();
+            let config = LintConfig::load(None, &[format!("ambiguous-target={}", severity)]);
+            run_lints(&manifest, &config.unwrap(), &mut report);
+            let warnings = report
+                .warnings
+                .into_iter()
+                .filter_map(|w| Some((false, w.strip_prefix("[ambiguous-target] ")?.to_string())));
+            let errors = report.errors.into_iter().filter_map(|e| match e {
+                ValidationError::LintError {
+                    rule: "ambiguous-target",
+                    message,
+                } => Some((true, message)),
+                _ => None,
+            });
+            warnings.chain(errors).collect::<Vec<_>>()
+        };
+        let message = "acme:kv/store of app is exported by redis, nats; linking redis, the first \
+                       declared, link it explicitly to choose another";
+        assert_eq!(lint("warn"), [(false, message.to_string())]);
+        assert_eq!(lint("error"), [(true, message.to_string())]);
+        assert!(lint("off").is_empty());
+    }
+
+    #[test]
+    fn component_policy_wins_over_ignore_list() {
+        let mut manifest = ConstructorManifest::new();
+        add(&mut manifest, "app", &["acme:kv/store"], &[]);
+        add(&mut manifest, "worker", &["acme:kv/store"], &[]);
+        manifest
+            .set_import_policy(None, "acme:kv/*", ImportPolicy::Ignored)
+            .unwrap();
+        manifest
+            .set_import_policy(Some("app"), "acme:kv/store", ImportPolicy::Optional)
+            .unwrap();
+
+        let policies: Vec<_> = manifest
+            .link_constructors
+            .iter()
+            .map(|link| manifest.import_policy(link))
+            .collect();
+        assert_eq!(
+            policies,
+            [Some(ImportPolicy::Optional), Some(ImportPolicy::Ignored)]
+        );
+    }
+
+    #[test]
+    fn import_policy_errors() {
+        let mut manifest = ConstructorManifest::new();
+        add(&mut manifest, "app", &["acme:kv/store"], &[]);
+        assert_eq!(
+            manifest.set_import_policy(Some("app"), "acme-kv", ImportPolicy::External),
+            Err("invalid external import acme-kv, expected namespace:package/interface".into())
+        );
+        assert_eq!(
+            manifest.set_import_policy(Some("app"), "acme:log/logging", ImportPolicy::Optional),
+            Err("Component app does not import interface acme:log/logging".into())
+        );
+    }
+
+    #[test]
+    fn versions_compatible_on_release_line() {
+        assert!(versions_compatible(Some("0.2.0"), Some("0.2.3")));
+        assert!(versions_compatible(Some("1.2.0"), Some("1.9.0")));
+        assert!(!versions_compatible(Some("0.2.3"), Some("0.2.0")));
+        assert!(!versions_compatible(Some("0.1.0"), Some("0.2.0")));
+        assert!(!versions_compatible(Some("1.0.0"), Some("2.0.0")));
+        assert!(!versions_compatible(Some("0.0.1"), Some("0.0.2")));
+        assert!(versions_compatible(
+            Some("0.2.0-draft"),
+            Some("0.2.0-draft")
+        ));
+        assert!(!versions_compatible(Some("0.2.0-draft"), Some("0.2.0")));
+        assert!(versions_compatible(None, Some("0.2.0")));
+        assert!(versions_compatible(Some("0.2.0"), None));
+    }
+}
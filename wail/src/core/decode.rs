@@ -1,8 +1,13 @@
 use std::path::Path;
 
 use wit_component::DecodedWasm;
-use wit_parser::{WorldItem, WorldKey};
+use wit_parser::{
+    InterfaceId, Resolve, Type, TypeDefKind, TypeOwner, WorldId, WorldItem, WorldKey,
+};
 
+use super::claims::read_claims;
+use super::extract::extract_interfaces;
+use super::metadata::read_metadata;
 use crate::models::{ComponentInfo, InterfaceInfo, PackageInfo};
 
 fn world_key_to_string(key: &WorldKey) -> String {
@@ -16,170 +21,282 @@ pub fn process_wasm_file(name: &str, path: &Path) -> anyhow::Result<ComponentInf
     println!("Processing WASM file for {}: {}", name, path.display());
     let bytes = std::fs::read(path)?;
 
-    if &bytes[0..4] != b"\0asm" {
+    if bytes.get(0..4) != Some(b"\0asm".as_slice()) {
         anyhow::bail!("Not a WASM file: {}", path.display());
     }
 
-    match wit_component::decode(&bytes)? {
-        DecodedWasm::Component(resolve, world_id) => {
-            let world = &resolve.worlds[world_id];
+    process_wasm_bytes(name, &bytes)
+}
 
-            // Debug world information
-            println!("\nWorld Information:");
-            println!("  World Name: {}", world.name);
+/// Reads interface information from an in-memory WASM component or WIT package, using the
+/// fast section scan where possible
+pub fn process_wasm_bytes(name: &str, bytes: &[u8]) -> anyhow::Result<ComponentInfo> {
+    let mut info = match extract_interfaces(bytes) {
+        Ok(Some(info)) => info,
+        Ok(None) => decode_resolve(name, bytes)?,
+        // The full decoder gives the more helpful error for malformed binaries
+        Err(e) => {
+            tracing::debug!("Section scan of {} failed: {}", name, e);
+            decode_resolve(name, bytes)?
+        }
+    };
+    info.claims = read_claims(name, bytes);
+    (info.producers, info.registry) = read_metadata(name, bytes);
+    Ok(info)
+}
 
-            // Debug interface definitions
-            println!("\nInterface Functions:");
-            for (id, interface_def) in resolve.interfaces.iter() {
-                println!("Interface[{}]:", id.index());
-                if let Some(pkg_id) = interface_def.package {
-                    let pkg = &resolve.packages[pkg_id];
-                    println!("  Package: {}.{}", pkg.name.namespace, pkg.name.name);
-                }
-                println!("  Name: {:?}", interface_def.name);
-                println!("  Functions:");
-                for (fname, func) in &interface_def.functions {
-                    println!("    {} -> {:?}", fname, func);
-                }
-            }
+/// Fully decodes a WASM component or WIT package into a `Resolve`, recording function
+/// names for structural checks
+pub fn decode_wasm_bytes(name: &str, bytes: &[u8]) -> anyhow::Result<ComponentInfo> {
+    let mut info = decode_resolve(name, bytes)?;
+    info.claims = read_claims(name, bytes);
+    (info.producers, info.registry) = read_metadata(name, bytes);
+    Ok(info)
+}
 
-            let mut info = ComponentInfo {
+/// Reads interface information from the world `world` of a WIT directory, for components
+/// that have not been built yet. Without `world`, the package must define a single world.
+pub fn process_wit_dir(
+    name: &str,
+    dir: &Path,
+    world: Option<&str>,
+) -> anyhow::Result<ComponentInfo> {
+    println!("Processing WIT world for {}: {}", name, dir.display());
+    let mut resolve = Resolve::default();
+    let (package, _) = resolve.push_dir(dir)?;
+    let world_id = resolve.select_world(package, world)?;
+    Ok(world_info(name, &resolve, world_id))
+}
+
+fn decode_resolve(name: &str, bytes: &[u8]) -> anyhow::Result<ComponentInfo> {
+    match wit_component::decode(bytes)? {
+        DecodedWasm::Component(resolve, world_id) => Ok(world_info(name, &resolve, world_id)),
+        DecodedWasm::WitPackage(resolve, pkg_id) => {
+            tracing::debug!("Found WIT package for {}", name);
+            // For WIT packages, we only set the package info
+            let package = &resolve.packages[pkg_id];
+            let info = ComponentInfo {
                 imports: Vec::new(),
                 exports: Vec::new(),
-                package: None,
+                package: Some(PackageInfo {
+                    namespace: package.name.namespace.clone(),
+                    name: package.name.name.clone(),
+                }),
+                claims: None,
+                producers: Default::default(),
+                registry: None,
+                version: None,
             };
+            tracing::debug!("  Package: {:?}", info.package);
+            Ok(info)
+        }
+    }
+}
+
+/// Interfaces imported and exported by a world
+fn world_info(name: &str, resolve: &Resolve, world_id: WorldId) -> ComponentInfo {
+    let world = &resolve.worlds[world_id];
+
+    // Walking every interface is only worth it when someone is reading the output
+    if tracing::enabled!(tracing::Level::DEBUG) {
+        tracing::debug!("World {} of {}", world.name, name);
+        for (id, interface_def) in resolve.interfaces.iter() {
+            let package = interface_def
+                .package
+                .map(|pkg_id| &resolve.packages[pkg_id].name);
+            tracing::debug!(
+                "Interface[{}] {:?} in {:?}",
+                id.index(),
+                interface_def.name,
+                package.map(|p| format!("{}.{}", p.namespace, p.name))
+            );
+            for (fname, func) in &interface_def.functions {
+                tracing::debug!("    {} -> {:?}", fname, func);
+            }
+        }
+    }
 
-            // Process imports
-            println!("\nAdding interfaces for component '{}':", name);
-            println!("  Imports:");
-
-            for (world_key, import) in &world.imports {
-                match import {
-                    WorldItem::Interface { id, .. } => {
-                        let interface_def = &resolve.interfaces[*id];
-                        if let Some(pkg_id) = interface_def.package {
-                            let pkg = &resolve.packages[pkg_id];
-
-                            let interface_name = if let Some(name) = &interface_def.name {
-                                name.clone()
-                            } else {
-                                match world_key {
-                                    WorldKey::Name(n) => n.clone(),
-                                    WorldKey::Interface(_) => {
-                                        if let Some((fname, _)) =
-                                            interface_def.functions.iter().next()
-                                        {
-                                            if fname.starts_with("[method]") {
-                                                fname.split('.').last().unwrap_or(fname).to_string()
-                                            } else {
-                                                fname.to_string()
-                                            }
-                                        } else {
-                                            format!("{}-{}", pkg.name.name, id.index())
-                                        }
+    let mut info = ComponentInfo {
+        imports: Vec::new(),
+        exports: Vec::new(),
+        package: None,
+        claims: None,
+        producers: Default::default(),
+        registry: None,
+        version: None,
+    };
+
+    // Process imports
+    for (world_key, import) in &world.imports {
+        match import {
+            WorldItem::Interface { id, .. } => {
+                let interface_def = &resolve.interfaces[*id];
+                if let Some(pkg_id) = interface_def.package {
+                    let pkg = &resolve.packages[pkg_id];
+
+                    let interface_name = if let Some(name) = &interface_def.name {
+                        name.clone()
+                    } else {
+                        match world_key {
+                            WorldKey::Name(n) => n.clone(),
+                            WorldKey::Interface(_) => {
+                                if let Some((fname, _)) = interface_def.functions.iter().next() {
+                                    if fname.starts_with("[method]") {
+                                        fname.split('.').last().unwrap_or(fname).to_string()
+                                    } else {
+                                        fname.to_string()
                                     }
+                                } else {
+                                    format!("{}-{}", pkg.name.name, id.index())
                                 }
-                            };
-
-                            println!(
-                                "    - {}:{}:{}",
-                                pkg.name.namespace, pkg.name.name, interface_name
-                            );
-
-                            info.imports.push(InterfaceInfo {
-                                name: interface_name,
-                                namespace: pkg.name.namespace.clone(),
-                                package: pkg.name.name.clone(),
-                            });
+                            }
                         }
-                    }
-                    WorldItem::Function(func) => {
-                        println!("Found function import: {}", world_key_to_string(world_key));
-                        println!("  Function: {:?}", func);
-                    }
-                    WorldItem::Type(_) => {
-                        println!("Found type import: {}", world_key_to_string(world_key));
-                    }
+                    };
+
+                    tracing::debug!(
+                        "{} {}:{}:{}",
+                        name,
+                        pkg.name.namespace,
+                        pkg.name.name,
+                        interface_name
+                    );
+
+                    info.imports.push(InterfaceInfo {
+                        name: interface_name,
+                        namespace: pkg.name.namespace.clone(),
+                        package: pkg.name.name.clone(),
+                        version: pkg.name.version.as_ref().map(ToString::to_string),
+                        functions: interface_def.functions.keys().cloned().collect(),
+                        resources_from: resources_from(resolve, *id),
+                    });
                 }
             }
+            WorldItem::Function(func) => {
+                tracing::debug!(
+                    "Found function import: {} {:?}",
+                    world_key_to_string(world_key),
+                    func
+                );
+            }
+            WorldItem::Type(_) => {
+                tracing::debug!("Found type import: {}", world_key_to_string(world_key));
+            }
+        }
+    }
 
-            // Process exports
-            println!("  Exports:");
-            for (world_key, export) in &world.exports {
-                match export {
-                    WorldItem::Interface { id, .. } => {
-                        let interface_def = &resolve.interfaces[*id];
-                        if let Some(pkg_id) = interface_def.package {
-                            let pkg = &resolve.packages[pkg_id];
-
-                            let interface_name = if let Some(name) = &interface_def.name {
-                                name.clone()
-                            } else {
-                                match world_key {
-                                    WorldKey::Name(n) => n.clone(),
-                                    WorldKey::Interface(_) => {
-                                        if let Some((fname, _)) =
-                                            interface_def.functions.iter().next()
-                                        {
-                                            if fname.starts_with("[method]") {
-                                                fname.split('.').last().unwrap_or(fname).to_string()
-                                            } else {
-                                                fname.to_string()
-                                            }
-                                        } else {
-                                            format!("{}-{}", pkg.name.name, id.index())
-                                        }
+    // Process exports
+    for (world_key, export) in &world.exports {
+        match export {
+            WorldItem::Interface { id, .. } => {
+                let interface_def = &resolve.interfaces[*id];
+                if let Some(pkg_id) = interface_def.package {
+                    let pkg = &resolve.packages[pkg_id];
+
+                    let interface_name = if let Some(name) = &interface_def.name {
+                        name.clone()
+                    } else {
+                        match world_key {
+                            WorldKey::Name(n) => n.clone(),
+                            WorldKey::Interface(_) => {
+                                if let Some((fname, _)) = interface_def.functions.iter().next() {
+                                    if fname.starts_with("[method]") {
+                                        fname.split('.').last().unwrap_or(fname).to_string()
+                                    } else {
+                                        fname.to_string()
                                     }
+                                } else {
+                                    format!("{}-{}", pkg.name.name, id.index())
                                 }
-                            };
-
-                            println!(
-                                "    - {}:{}:{}",
-                                pkg.name.namespace, pkg.name.name, interface_name
-                            );
-
-                            info.exports.push(InterfaceInfo {
-                                name: interface_name,
-                                namespace: pkg.name.namespace.clone(),
-                                package: pkg.name.name.clone(),
-                            });
+                            }
                         }
-                    }
-                    WorldItem::Function(func) => {
-                        println!("Found function export: {}", world_key_to_string(world_key));
-                        println!("  Function: {:?}", func);
-                    }
-                    WorldItem::Type(_) => {
-                        println!("Found type export: {}", world_key_to_string(world_key));
-                    }
+                    };
+
+                    tracing::debug!(
+                        "{} {}:{}:{}",
+                        name,
+                        pkg.name.namespace,
+                        pkg.name.name,
+                        interface_name
+                    );
+
+                    info.exports.push(InterfaceInfo {
+                        name: interface_name,
+                        namespace: pkg.name.namespace.clone(),
+                        package: pkg.name.name.clone(),
+                        version: pkg.name.version.as_ref().map(ToString::to_string),
+                        functions: interface_def.functions.keys().cloned().collect(),
+                        resources_from: resources_from(resolve, *id),
+                    });
                 }
             }
-
-            // Add component package info if available
-            if let Some(pkg_id) = world.package {
-                let package = &resolve.packages[pkg_id];
-                info.package = Some(PackageInfo {
-                    namespace: package.name.namespace.clone(),
-                    name: package.name.name.clone(),
-                });
+            WorldItem::Function(func) => {
+                tracing::debug!(
+                    "Found function export: {} {:?}",
+                    world_key_to_string(world_key),
+                    func
+                );
             }
+            WorldItem::Type(_) => {
+                tracing::debug!("Found type export: {}", world_key_to_string(world_key));
+            }
+        }
+    }
 
-            Ok(info)
+    // Add component package info if available
+    if let Some(pkg_id) = world.package {
+        let package = &resolve.packages[pkg_id];
+        info.package = Some(PackageInfo {
+            namespace: package.name.namespace.clone(),
+            name: package.name.name.clone(),
+        });
+    }
+
+    info
+}
+
+/// Interfaces other than `id` that define the resource types `id` uses
+fn resources_from(resolve: &Resolve, id: InterfaceId) -> Vec<String> {
+    let mut interfaces = Vec::new();
+    for &type_id in resolve.interfaces[id].types.values() {
+        // Follow `use` aliases to the type's definition
+        let mut type_id = type_id;
+        while let TypeDefKind::Type(Type::Id(aliased)) = resolve.types[type_id].kind {
+            type_id = aliased;
         }
-        DecodedWasm::WitPackage(resolve, pkg_id) => {
-            println!("Found WIT package for {}", name);
-            // For WIT packages, we only set the package info
-            let package = &resolve.packages[pkg_id];
-            let info = ComponentInfo {
-                imports: Vec::new(),
-                exports: Vec::new(),
-                package: Some(PackageInfo {
-                    namespace: package.name.namespace.clone(),
-                    name: package.name.name.clone(),
-                }),
-            };
-            println!("  Package: {:?}", info.package);
-            Ok(info)
+        let definition = &resolve.types[type_id];
+        let TypeOwner::Interface(owner) = definition.owner else {
+            continue;
+        };
+        if owner == id || !matches!(definition.kind, TypeDefKind::Resource) {
+            continue;
+        }
+        if let Some(name) = resolve.id_of(owner) {
+            let name = name
+                .split_once('@')
+                .map_or(name.clone(), |(n, _)| n.to_string());
+            if !interfaces.contains(&name) {
+                interfaces.push(name);
+            }
+        }
+    }
+    interfaces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_files_are_not_wasm() {
+        let path = std::env::temp_dir().join(format!("wail-decode-{}.wasm", std::process::id()));
+        for bytes in [&b""[..], b"\0as", b"\0asn\x01\0\0\0"] {
+            std::fs::write(&path, bytes).unwrap();
+            let error = process_wasm_file("short", &path).unwrap_err();
+            assert!(
+                error.to_string().starts_with("Not a WASM file"),
+                "{:?}",
+                bytes
+            );
         }
     }
 }
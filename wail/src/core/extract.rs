@@ -0,0 +1,241 @@
+use wasmparser::{
+    Chunk, ComponentAlias, ComponentExternalKind, ComponentOuterAliasKind, ComponentType,
+    ComponentTypeRef, Encoding, InstanceTypeDeclaration, Parser, Payload, TypeBounds,
+};
+
+use crate::models::{ComponentInfo, InterfaceIdentifier, InterfaceInfo};
+
+/// Lists the interfaces a component imports and exports by scanning only its top-level
+/// import and export sections, without building a `Resolve`.
+///
+/// Returns `Ok(None)` for binaries the scan cannot describe, such as WIT packages, which
+/// need the full decode. Only interfaces named by ID (`namespace:package/interface`) are
+/// listed and function names are not recorded. `resources_from` is only recorded for
+/// imports.
+pub fn extract_interfaces(bytes: &[u8]) -> anyhow::Result<Option<ComponentInfo>> {
+    let mut info = ComponentInfo {
+        imports: Vec::new(),
+        exports: Vec::new(),
+        package: None,
+        claims: None,
+        producers: Default::default(),
+        registry: None,
+        version: None,
+    };
+    let mut exports_types = false;
+    // The component's type and instance index spaces, as far as resource origins need them
+    let mut types: Vec<TypeSource> = Vec::new();
+    let mut instances: Vec<Option<Imported>> = Vec::new();
+
+    let mut parser = Parser::new(0);
+    let mut data = bytes;
+    loop {
+        let payload = match parser.parse(data, true)? {
+            Chunk::Parsed { consumed, payload } => {
+                data = &data[consumed..];
+                payload
+            }
+            // Not possible with `eof = true`
+            Chunk::NeedMoreData(_) => anyhow::bail!("Truncated WASM binary"),
+        };
+
+        match payload {
+            Payload::Version { encoding, .. } if encoding != Encoding::Component => {
+                anyhow::bail!("Not a component, core WASM modules have no interfaces");
+            }
+            // Nested modules and components are internal to the component, skip their bytes
+            Payload::ModuleSection {
+                unchecked_range, ..
+            }
+            | Payload::ComponentSection {
+                unchecked_range, ..
+            } => {
+                data = &data[unchecked_range.len()..];
+            }
+            Payload::ComponentTypeSection(reader) => {
+                for ty in reader {
+                    types.push(match ty? {
+                        ComponentType::Instance(declarations) => instance_type(&declarations),
+                        _ => TypeSource::Other,
+                    });
+                }
+            }
+            Payload::ComponentAliasSection(reader) => {
+                for alias in reader {
+                    match alias? {
+                        ComponentAlias::InstanceExport {
+                            kind: ComponentExternalKind::Type,
+                            instance_index,
+                            name,
+                        } => types.push(TypeSource::InstanceExport {
+                            instance: instance_index,
+                            name: name.to_string(),
+                        }),
+                        ComponentAlias::InstanceExport {
+                            kind: ComponentExternalKind::Instance,
+                            ..
+                        } => instances.push(None),
+                        ComponentAlias::Outer {
+                            kind: ComponentOuterAliasKind::Type,
+                            ..
+                        } => types.push(TypeSource::Other),
+                        _ => {}
+                    }
+                }
+            }
+            Payload::ComponentInstanceSection(reader) => {
+                for instance in reader {
+                    instance?;
+                    instances.push(None);
+                }
+            }
+            Payload::ComponentImportSection(reader) => {
+                for import in reader {
+                    let import = import?;
+                    match import.ty {
+                        ComponentTypeRef::Instance(index) => {
+                            instances.push(Some(Imported {
+                                name: import.name.0.to_string(),
+                                ty: index,
+                            }));
+                            let Some(mut interface) = interface_info(import.name.0) else {
+                                continue;
+                            };
+                            interface.resources_from = resource_origins(
+                                &types,
+                                &instances,
+                                index,
+                                &interface_id(&interface),
+                            );
+                            info.imports.push(interface);
+                        }
+                        ComponentTypeRef::Type(_) => types.push(TypeSource::Other),
+                        _ => {}
+                    }
+                }
+            }
+            Payload::ComponentExportSection(reader) => {
+                for export in reader {
+                    let export = export?;
+                    match export.kind {
+                        ComponentExternalKind::Instance => {
+                            instances.push(None);
+                            info.exports.extend(interface_info(export.name.0))
+                        }
+                        ComponentExternalKind::Type => {
+                            types.push(TypeSource::Other);
+                            exports_types = true
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Payload::End(_) => break,
+            _ => {}
+        }
+    }
+
+    // WIT packages are encoded as components exporting only types
+    if exports_types && info.imports.is_empty() && info.exports.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(info))
+}
+
+/// `wasi:keyvalue/store@0.2.0-draft` as an `InterfaceInfo`, `None` for plain names
+fn interface_info(name: &str) -> Option<InterfaceInfo> {
+    let id = InterfaceIdentifier::parse(name)?;
+    Some(InterfaceInfo {
+        name: id.name,
+        namespace: id.namespace,
+        package: id.package,
+        version: name.split_once('@').map(|(_, version)| version.to_string()),
+        functions: Vec::new(),
+        resources_from: Vec::new(),
+    })
+}
+
+/// Where an entry of the component's type index space came from
+enum TypeSource {
+    Instance {
+        /// Component types the instance type aliases from the enclosing component, which is
+        /// how it refers to types defined by other instances
+        aliased: Vec<u32>,
+        /// Names of the resource types the instance type exports
+        resources: Vec<String>,
+    },
+    /// The type `name` exported by the instance with this index
+    InstanceExport {
+        instance: u32,
+        name: String,
+    },
+    Other,
+}
+
+/// An imported instance: its import name and instance type index
+struct Imported {
+    name: String,
+    ty: u32,
+}
+
+fn instance_type(declarations: &[InstanceTypeDeclaration]) -> TypeSource {
+    let mut aliased = Vec::new();
+    let mut resources = Vec::new();
+    for declaration in declarations {
+        match declaration {
+            InstanceTypeDeclaration::Alias(ComponentAlias::Outer {
+                kind: ComponentOuterAliasKind::Type,
+                count: 1,
+                index,
+            }) => aliased.push(*index),
+            InstanceTypeDeclaration::Export {
+                name,
+                ty: ComponentTypeRef::Type(TypeBounds::SubResource),
+            } => resources.push(name.0.to_string()),
+            _ => {}
+        }
+    }
+    TypeSource::Instance { aliased, resources }
+}
+
+/// The interfaces other than `own_id` whose resource types the instance type at
+/// `type_index` uses. Other types used across interfaces are aliased too, but they are
+/// compared structurally and do not tie the two interfaces to the same target.
+fn resource_origins(
+    types: &[TypeSource],
+    instances: &[Option<Imported>],
+    type_index: u32,
+    own_id: &str,
+) -> Vec<String> {
+    let Some(TypeSource::Instance { aliased, .. }) = types.get(type_index as usize) else {
+        return Vec::new();
+    };
+    let mut origins = Vec::new();
+    for &index in aliased {
+        let Some(TypeSource::InstanceExport { instance, name }) = types.get(index as usize) else {
+            continue;
+        };
+        let Some(Some(imported)) = instances.get(*instance as usize) else {
+            continue;
+        };
+        let is_resource = matches!(
+            types.get(imported.ty as usize),
+            Some(TypeSource::Instance { resources, .. }) if resources.contains(name)
+        );
+        let Some(id) = InterfaceIdentifier::parse(&imported.name).map(|id| id.to_string()) else {
+            continue;
+        };
+        if is_resource && id != own_id && !origins.contains(&id) {
+            origins.push(id);
+        }
+    }
+    origins
+}
+
+/// `namespace:package/interface` of `interface`, without the version
+fn interface_id(interface: &InterfaceInfo) -> String {
+    format!(
+        "{}:{}/{}",
+        interface.namespace, interface.package, interface.name
+    )
+}
@@ -0,0 +1,396 @@
+use anyhow::{bail, Context};
+
+/// A completed HTTP response. Redirects have already been followed.
+pub struct Response {
+    pub status: u16,
+    /// Headers with lowercase names
+    headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// Downloads `url`, failing unless the server answers with a success status
+pub fn download(url: &str) -> anyhow::Result<Vec<u8>> {
+    let response = get(url, &[])?;
+    if !response.is_success() {
+        bail!("failed to download {}: status {}", url, response.status);
+    }
+    Ok(response.body)
+}
+
+/// Sends a GET request with ureq in the native build. Error statuses are returned as
+/// responses rather than errors.
+#[cfg(feature = "native")]
+pub fn get(url: &str, headers: &[(&str, &str)]) -> anyhow::Result<Response> {
+    use std::io::Read;
+
+    let mut request = ureq::get(url);
+    for (name, value) in headers {
+        request = request.set(name, value);
+    }
+    let response = match request.call() {
+        Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+        // ureq errors name the URL
+        Err(e) => return Err(e.into()),
+    };
+
+    let status = response.status();
+    let headers = response
+        .headers_names()
+        .into_iter()
+        .filter_map(|name| {
+            let value = response.header(&name)?.to_string();
+            Some((name, value))
+        })
+        .collect();
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut body)
+        .with_context(|| format!("failed to read the response from {}", url))?;
+    Ok(Response {
+        status,
+        headers,
+        body,
+    })
+}
+
+/// Sends a GET request through the plugin's `wasi:http/outgoing-handler` import. Error
+/// statuses are returned as responses rather than errors.
+#[cfg(not(feature = "native"))]
+pub fn get(url: &str, headers: &[(&str, &str)]) -> anyhow::Result<Response> {
+    use std::io::Read;
+
+    use crate::wasi::http::outgoing_handler;
+    use crate::wasi::http::types::{Fields, IncomingBody, Method, OutgoingRequest, Scheme};
+
+    /// Redirects followed before giving up
+    const MAX_REDIRECTS: usize = 5;
+
+    let mut url = url.to_string();
+    let mut headers = headers.to_vec();
+    for _ in 0..=MAX_REDIRECTS {
+        let (scheme, rest) = match url.split_once("://") {
+            Some(("https", rest)) => (Scheme::Https, rest),
+            Some(("http", rest)) => (Scheme::Http, rest),
+            _ => bail!("unsupported URL {}", url),
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, "/"),
+        };
+
+        let fields = Fields::new();
+        for (name, value) in &headers {
+            fields
+                .append(&name.to_string(), &value.as_bytes().to_vec())
+                .map_err(|e| anyhow::anyhow!("invalid header {}: {:?}", name, e))?;
+        }
+        let request = OutgoingRequest::new(fields);
+        let configured = request
+            .set_method(&Method::Get)
+            .and_then(|_| request.set_scheme(Some(&scheme)))
+            .and_then(|_| request.set_authority(Some(authority)))
+            .and_then(|_| request.set_path_with_query(Some(path)));
+        if configured.is_err() {
+            bail!("invalid URL {}", url);
+        }
+
+        let future = outgoing_handler::handle(request, None)
+            .map_err(|e| anyhow::anyhow!("failed to download {}: {:?}", url, e))?;
+        future.subscribe().block();
+        let response = match future.get() {
+            Some(Ok(Ok(response))) => response,
+            Some(Ok(Err(e))) => bail!("failed to download {}: {:?}", url, e),
+            _ => bail!("failed to download {}: no response", url),
+        };
+
+        let status = response.status();
+        let response_headers: Vec<_> = response
+            .headers()
+            .entries()
+            .into_iter()
+            .map(|(name, value)| {
+                (
+                    name.to_ascii_lowercase(),
+                    String::from_utf8_lossy(&value).to_string(),
+                )
+            })
+            .collect();
+        if (300..400).contains(&status) {
+            let Some((_, location)) = response_headers.iter().find(|(name, _)| name == "location")
+            else {
+                bail!("{} redirected without a location", url);
+            };
+            url = redirect_url(&url, location);
+            // Credentials are not forwarded to the redirect target, which is usually a
+            // presigned storage URL
+            headers.retain(|(name, _)| !name.eq_ignore_ascii_case("authorization"));
+            continue;
+        }
+
+        let body = response
+            .consume()
+            .map_err(|_| anyhow::anyhow!("failed to read the response from {}", url))?;
+        let mut stream = body
+            .stream()
+            .map_err(|_| anyhow::anyhow!("failed to read the response from {}", url))?;
+        let mut bytes = Vec::new();
+        crate::InputStreamReader::from(&mut stream)
+            .read_to_end(&mut bytes)
+            .with_context(|| format!("failed to read the response from {}", url))?;
+        drop(stream);
+        IncomingBody::finish(body);
+        return Ok(Response {
+            status,
+            headers: response_headers,
+            body: bytes,
+        });
+    }
+    bail!("too many redirects downloading {}", url)
+}
+
+/// The target of a redirect from `url`: `location` resolved against `url` as a relative
+/// reference (RFC 3986 §5.2)
+#[cfg(any(test, not(feature = "native")))]
+fn redirect_url(url: &str, location: &str) -> String {
+    let has_scheme = location.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    });
+    if has_scheme {
+        return location.to_string();
+    }
+    let (scheme, rest) = url.split_once("://").unwrap_or(("https", url));
+    if let Some(target) = location.strip_prefix("//") {
+        return format!("{}://{}", scheme, target);
+    }
+
+    let rest = rest.split('#').next().unwrap_or(rest);
+    let authority_end = rest.find(['/', '?']).unwrap_or(rest.len());
+    let (authority, rest) = rest.split_at(authority_end);
+    let (path, query) = rest.split_at(rest.find('?').unwrap_or(rest.len()));
+    let base = format!("{}://{}", scheme, authority);
+    if location.is_empty() || location.starts_with('#') {
+        return format!("{}{}{}{}", base, path, query, location);
+    }
+    if location.starts_with('?') {
+        return format!("{}{}{}", base, path, location);
+    }
+
+    let (target, suffix) = location.split_at(location.find(['?', '#']).unwrap_or(location.len()));
+    let merged = if target.starts_with('/') {
+        target.to_string()
+    } else {
+        // Relative paths replace the last segment of the base path
+        let directory = path.rfind('/').map_or("", |end| &path[..end]);
+        format!("{}/{}", directory, target)
+    };
+    format!("{}{}{}", base, remove_dot_segments(&merged), suffix)
+}
+
+/// Resolves `.` and `..` segments of an absolute path
+#[cfg(any(test, not(feature = "native")))]
+fn remove_dot_segments(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    let mut trailing_slash = false;
+    for segment in path.split('/').skip(1) {
+        trailing_slash = matches!(segment, "." | "..");
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    let mut resolved = format!("/{}", segments.join("/"));
+    if trailing_slash && !segments.is_empty() {
+        resolved.push('/');
+    }
+    resolved
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::core::cache::ComponentCache;
+
+    const BODY: &[u8] = b"\0asm component bytes";
+
+    /// Serves `/component.wasm`, a redirect to it from `/moved` and a 404 for anything else
+    /// on a local port, counting the requests for the component
+    fn serve() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                // Skip the request headers
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                    line.clear();
+                }
+
+                let path = request_line.split(' ').nth(1).unwrap_or("/");
+                let (status, headers, body): (&str, &str, &[u8]) = match path {
+                    "/component.wasm" => {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        ("200 OK", "", BODY)
+                    }
+                    "/moved" => ("302 Found", "Location: /component.wasm\r\n", b""),
+                    _ => ("404 Not Found", "", b"not found"),
+                };
+                let head = format!(
+                    "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    headers,
+                    body.len()
+                );
+                let _ = stream
+                    .write_all(head.as_bytes())
+                    .and_then(|_| stream.write_all(body));
+            }
+        });
+        (base, hits)
+    }
+
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wail-fetch-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn digest(bytes: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(bytes))
+    }
+
+    #[test]
+    fn download_matching_sha256() {
+        let (base, _) = serve();
+        let mut cache = ComponentCache::with_dir(cache_dir("match"));
+        let url = format!("{}/component.wasm", base);
+        let path = cache.download("app", &url, &digest(BODY)).unwrap();
+        assert_eq!(std::fs::read(path).unwrap(), BODY);
+    }
+
+    #[test]
+    fn download_rejects_sha256_mismatch() {
+        let (base, _) = serve();
+        let dir = cache_dir("mismatch");
+        let mut cache = ComponentCache::with_dir(dir.clone());
+        let url = format!("{}/component.wasm", base);
+        let error = cache.download("app", &url, &digest(b"other")).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("does not match the declared sha256"));
+        assert!(!dir.join("downloads").exists());
+    }
+
+    #[test]
+    fn download_reuses_cached_copy() {
+        let (base, hits) = serve();
+        let mut cache = ComponentCache::with_dir(cache_dir("reuse"));
+        let url = format!("{}/component.wasm", base);
+        let first = cache.download("app", &url, &digest(BODY)).unwrap();
+        let second = cache.download("app", &url, &digest(BODY)).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn download_follows_redirect() {
+        let (base, hits) = serve();
+        let bytes = download(&format!("{}/moved", base)).unwrap();
+        assert_eq!(bytes, BODY);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn download_fails_on_error_status() {
+        let (base, _) = serve();
+        let url = format!("{}/missing.wasm", base);
+        let error = download(&url).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("failed to download {}: status 404", url)
+        );
+        assert_eq!(get(&url, &[]).unwrap().status, 404);
+    }
+
+    #[test]
+    fn redirect_url_resolves_against_authority() {
+        assert_eq!(
+            redirect_url("https://example.com", "/blob/1"),
+            "https://example.com/blob/1"
+        );
+        assert_eq!(
+            redirect_url("https://example.com:8443/a/b?c=/d", "/blob/1"),
+            "https://example.com:8443/blob/1"
+        );
+        assert_eq!(
+            redirect_url("http://example.com/a", "//cdn.example.com/blob"),
+            "http://cdn.example.com/blob"
+        );
+        assert_eq!(
+            redirect_url("http://example.com/a", "https://s3.example.com/blob?sig=1"),
+            "https://s3.example.com/blob?sig=1"
+        );
+
+        // Relative paths resolve against the directory of the request path
+        let base = "https://example.com:8443/v2/app/blobs/sha256:1?x=1#top";
+        let cases = [
+            ("blob/1", "https://example.com:8443/v2/app/blobs/blob/1"),
+            (
+                "./blob?sig=1",
+                "https://example.com:8443/v2/app/blobs/blob?sig=1",
+            ),
+            ("../x", "https://example.com:8443/v2/app/x"),
+            ("../../../../../x", "https://example.com:8443/x"),
+            ("..", "https://example.com:8443/v2/app/"),
+            (".", "https://example.com:8443/v2/app/blobs/"),
+            ("/a/./b/../c", "https://example.com:8443/a/c"),
+            ("?y=2", "https://example.com:8443/v2/app/blobs/sha256:1?y=2"),
+            ("", "https://example.com:8443/v2/app/blobs/sha256:1?x=1"),
+        ];
+        for (location, expected) in cases {
+            assert_eq!(redirect_url(base, location), expected, "{}", location);
+        }
+        // A base without a path resolves against the root
+        assert_eq!(
+            redirect_url("https://example.com", "blob/1"),
+            "https://example.com/blob/1"
+        );
+        assert_eq!(
+            redirect_url("https://example.com?q=1", "blob/1"),
+            "https://example.com/blob/1"
+        );
+    }
+}
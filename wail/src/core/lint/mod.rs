@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use super::{ConstructorManifest, ValidationError, ValidationReport};
+
+mod rules;
+pub use rules::RULES;
+
+/// How a lint finding is reported
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Off,
+    Warn,
+    Error,
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Severity::Off),
+            "warn" => Ok(Severity::Warn),
+            "error" => Ok(Severity::Error),
+            _ => Err(format!(
+                "unknown severity {}, expected off, warn or error",
+                s
+            )),
+        }
+    }
+}
+
+/// A manifest-level check run after links are resolved
+pub trait LintRule: Sync {
+    /// Stable ID used in lint config files and `--lint` flags
+    fn id(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+    fn default_severity(&self) -> Severity;
+    /// Returns one message per finding
+    fn check(&self, manifest: &ConstructorManifest) -> Vec<String>;
+}
+
+/// Severity overrides, read from a lint config file and `--lint <rule>=<severity>` flags
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LintConfig {
+    #[serde(default)]
+    rules: HashMap<String, Severity>,
+}
+
+impl LintConfig {
+    /// Loads `path` if given, then applies `overrides` on top
+    pub fn load(path: Option<&Path>, overrides: &[String]) -> Result<Self, String> {
+        let mut config = match path {
+            Some(path) => {
+                let content = std::fs::read_to_string(path)
+                    .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+                serde_yaml::from_str(&content)
+                    .map_err(|e| format!("failed to parse {}: {}", path.display(), e))?
+            }
+            None => LintConfig::default(),
+        };
+
+        for flag in overrides {
+            let Some((rule, severity)) = flag.split_once('=') else {
+                return Err(format!(
+                    "invalid --lint {}, expected <rule>=<severity>",
+                    flag
+                ));
+            };
+            config.rules.insert(rule.to_string(), severity.parse()?);
+        }
+
+        for rule in config.rules.keys() {
+            if !RULES.iter().any(|r| r.id() == rule) {
+                let known: Vec<_> = RULES.iter().map(|r| r.id()).collect();
+                return Err(format!(
+                    "unknown lint rule {} (known rules: {})",
+                    rule,
+                    known.join(", ")
+                ));
+            }
+        }
+        Ok(config)
+    }
+
+    fn severity(&self, rule: &dyn LintRule) -> Severity {
+        self.rules
+            .get(rule.id())
+            .copied()
+            .unwrap_or_else(|| rule.default_severity())
+    }
+}
+
+/// Runs every enabled rule and records findings in `report`
+pub fn run_lints(
+    manifest: &ConstructorManifest,
+    config: &LintConfig,
+    report: &mut ValidationReport,
+) {
+    for rule in RULES {
+        let severity = config.severity(*rule);
+        if severity == Severity::Off {
+            continue;
+        }
+        for message in rule.check(manifest) {
+            match severity {
+                Severity::Error => report.add_error(ValidationError::LintError {
+                    rule: rule.id(),
+                    message,
+                }),
+                _ => report.add_warning(format!("[{}] {}", rule.id(), message)),
+            }
+        }
+    }
+}
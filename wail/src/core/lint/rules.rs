@@ -0,0 +1,340 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+use wadm_types::Properties;
+
+use super::{LintRule, Severity};
+use crate::core::{ConstructorManifest, LinkConstructor, RUNTIME_INTERFACES};
+
+/// Every rule, in the order findings are reported
+pub const RULES: &[&dyn LintRule] = &[
+    &DuplicateComponent,
+    &SelfLink,
+    &CapabilityExport,
+    &Unreachable,
+    &UnusedExport,
+    &MultipleLinks,
+    &AmbiguousTarget,
+];
+
+fn is_capability(manifest: &ConstructorManifest, name: &str) -> bool {
+    manifest
+        .get_component(name)
+        .is_some_and(|c| matches!(c.properties, Properties::Capability { .. }))
+}
+
+fn is_runtime_link(link: &LinkConstructor) -> bool {
+    link.namespace == "wasi"
+        && RUNTIME_INTERFACES
+            .iter()
+            .any(|(_, pkg, name)| pkg == &link.package && name == &link.interfaces[0])
+}
+
+/// Resolved links, excluding interfaces the runtime provides
+fn resolved_links(
+    manifest: &ConstructorManifest,
+) -> impl Iterator<Item = (&LinkConstructor, &str)> {
+    manifest
+        .link_constructors
+        .iter()
+        .filter(|link| !is_runtime_link(link))
+        .filter_map(|link| {
+            link.post_component_id
+                .as_deref()
+                .map(|target| (link, target))
+        })
+}
+
+pub struct DuplicateComponent;
+
+impl LintRule for DuplicateComponent {
+    fn id(&self) -> &'static str {
+        "duplicate-component"
+    }
+
+    fn description(&self) -> &'static str {
+        "A component name is declared more than once in the same input"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, manifest: &ConstructorManifest) -> Vec<String> {
+        let mut counts: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+        for (source, name) in &manifest.declarations {
+            *counts.entry((source.as_str(), name.as_str())).or_default() += 1;
+        }
+        counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|((source, name), count)| {
+                format!(
+                    "{} is declared {} times in {}; later declarations replace earlier ones",
+                    name, count, source
+                )
+            })
+            .collect()
+    }
+}
+
+pub struct SelfLink;
+
+impl LintRule for SelfLink {
+    fn id(&self) -> &'static str {
+        "self-link"
+    }
+
+    fn description(&self) -> &'static str {
+        "A component links an import to itself"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, manifest: &ConstructorManifest) -> Vec<String> {
+        resolved_links(manifest)
+            .filter(|(link, target)| link.pre_component_id == *target)
+            .map(|(link, _)| {
+                format!(
+                    "{} links {}:{}/{} to itself",
+                    link.pre_component_id, link.namespace, link.package, link.interfaces[0]
+                )
+            })
+            .collect()
+    }
+}
+
+pub struct CapabilityExport;
+
+impl LintRule for CapabilityExport {
+    fn id(&self) -> &'static str {
+        "capability-export"
+    }
+
+    fn description(&self) -> &'static str {
+        "A link targets a capability provider with known interfaces that does not export the interface"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, manifest: &ConstructorManifest) -> Vec<String> {
+        // Custom providers missing from the catalog have no known interfaces to check against
+        let described = |target: &str| {
+            manifest
+                .component_interfaces
+                .get(target)
+                .is_some_and(|info| !info.imports.is_empty() || !info.exports.is_empty())
+        };
+        resolved_links(manifest)
+            .filter(|(_, target)| is_capability(manifest, target) && described(target))
+            .filter(|(link, target)| manifest.matching_export(target, link).is_none())
+            .map(|(link, target)| {
+                format!(
+                    "{} links {}:{}/{} to capability {}, which does not export it",
+                    link.pre_component_id, link.namespace, link.package, link.interfaces[0], target
+                )
+            })
+            .collect()
+    }
+}
+
+pub struct Unreachable;
+
+impl LintRule for Unreachable {
+    fn id(&self) -> &'static str {
+        "unreachable"
+    }
+
+    fn description(&self) -> &'static str {
+        "A component cannot be invoked through links from any capability provider"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn check(&self, manifest: &ConstructorManifest) -> Vec<String> {
+        let capabilities: Vec<&str> = manifest
+            .spec
+            .components
+            .iter()
+            .filter(|c| matches!(c.properties, Properties::Capability { .. }))
+            .map(|c| c.name.as_str())
+            .collect();
+        // Without providers nothing drives the application, so every component would match
+        if capabilities.is_empty() {
+            return Vec::new();
+        }
+
+        let mut reachable: HashSet<&str> = capabilities.iter().copied().collect();
+        let mut queue: VecDeque<&str> = capabilities.into_iter().collect();
+        while let Some(source) = queue.pop_front() {
+            for (_, target) in
+                resolved_links(manifest).filter(|(l, _)| l.pre_component_id == source)
+            {
+                if reachable.insert(target) {
+                    queue.push_back(target);
+                }
+            }
+        }
+
+        manifest
+            .spec
+            .components
+            .iter()
+            .filter(|c| !reachable.contains(c.name.as_str()))
+            .map(|c| {
+                format!(
+                    "{} is not reachable from any capability provider and will never be invoked",
+                    c.name
+                )
+            })
+            .collect()
+    }
+}
+
+pub struct UnusedExport;
+
+impl LintRule for UnusedExport {
+    fn id(&self) -> &'static str {
+        "unused-export"
+    }
+
+    fn description(&self) -> &'static str {
+        "A component exports an interface no link uses"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn check(&self, manifest: &ConstructorManifest) -> Vec<String> {
+        // Keyed by the exported interface, which differs from the import for aliased links
+        let used: HashSet<(&str, &str, &str, &str)> = resolved_links(manifest)
+            .filter_map(|(link, target)| {
+                let (export, _) = manifest.matching_export(target, link)?;
+                Some((
+                    target,
+                    export.namespace.as_str(),
+                    export.package.as_str(),
+                    export.name.as_str(),
+                ))
+            })
+            .collect();
+
+        let mut findings = Vec::new();
+        for component in &manifest.spec.components {
+            // Providers export whole packages, most applications use only part of them
+            if is_capability(manifest, &component.name) {
+                continue;
+            }
+            let Some(info) = manifest.component_interfaces.get(&component.name) else {
+                continue;
+            };
+            for export in &info.exports {
+                // wasi:cli exports are invoked by the host
+                if export.namespace == "wasi" && export.package == "cli" {
+                    continue;
+                }
+                let key = (
+                    component.name.as_str(),
+                    export.namespace.as_str(),
+                    export.package.as_str(),
+                    export.name.as_str(),
+                );
+                if !used.contains(&key) {
+                    findings.push(format!(
+                        "{} exports {}:{}/{} but nothing links to it",
+                        component.name, export.namespace, export.package, export.name
+                    ));
+                }
+            }
+        }
+        findings
+    }
+}
+
+pub struct MultipleLinks;
+
+impl LintRule for MultipleLinks {
+    fn id(&self) -> &'static str {
+        "multiple-links"
+    }
+
+    fn description(&self) -> &'static str {
+        "An import is bound by more than one link (named links do this on purpose)"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Off
+    }
+
+    fn check(&self, manifest: &ConstructorManifest) -> Vec<String> {
+        let mut targets: BTreeMap<(&str, &str, &str, &str), Vec<String>> = BTreeMap::new();
+        for (link, target) in resolved_links(manifest) {
+            targets
+                .entry((
+                    link.pre_component_id.as_str(),
+                    link.namespace.as_str(),
+                    link.package.as_str(),
+                    link.interfaces[0].as_str(),
+                ))
+                .or_default()
+                .push(format!("{} ({})", target, link.link_name()));
+        }
+        targets
+            .into_iter()
+            .filter(|(_, targets)| targets.len() > 1)
+            .map(|((source, namespace, package, interface), targets)| {
+                format!(
+                    "{} imports {}:{}/{} through {} links: {}",
+                    source,
+                    namespace,
+                    package,
+                    interface,
+                    targets.len(),
+                    targets.join(", ")
+                )
+            })
+            .collect()
+    }
+}
+
+pub struct AmbiguousTarget;
+
+impl LintRule for AmbiguousTarget {
+    fn id(&self) -> &'static str {
+        "ambiguous-target"
+    }
+
+    fn description(&self) -> &'static str {
+        "An import exported by several components is linked to the first declared"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn check(&self, manifest: &ConstructorManifest) -> Vec<String> {
+        manifest
+            .ambiguous_targets
+            .iter()
+            .filter_map(|(index, names)| {
+                let link = manifest.link_constructors.get(*index)?;
+                Some(format!(
+                    "{}:{}/{} of {} is exported by {}; linking {}, the first declared, link it \
+                     explicitly to choose another",
+                    link.namespace,
+                    link.package,
+                    link.interfaces[0],
+                    link.pre_component_id,
+                    names.join(", "),
+                    names[0]
+                ))
+            })
+            .collect()
+    }
+}
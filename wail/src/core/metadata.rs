@@ -0,0 +1,291 @@
+use std::collections::BTreeMap;
+
+use wasm_metadata::{Metadata, Producers, RegistryMetadata};
+
+use crate::models::{ComponentInfo, RegistryInfo};
+
+/// Prefix of the manifest annotations and labels describing components. wadm components have
+/// no annotations of their own, so keys are `<prefix>/<component>.<field>`.
+pub const ANNOTATION_PREFIX: &str = "wail.wasmcloud.dev";
+
+/// Where component metadata is copied to in the generated manifest
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum MetadataOutput {
+    Annotations,
+    /// Values are reduced to the characters labels allow; descriptions are left out
+    Labels,
+}
+
+/// Reads the `producers` and `registry-metadata` custom sections.
+///
+/// Producers of the component and every module nested in it are merged, as field
+/// (`language`, `processed-by`, `sdk`) to tool name to version, since the source language is
+/// usually only recorded by the inner core module. Registry metadata is taken from the
+/// outermost component or module that has it.
+pub fn read_metadata(
+    name: &str,
+    bytes: &[u8],
+) -> (
+    BTreeMap<String, BTreeMap<String, String>>,
+    Option<RegistryInfo>,
+) {
+    let metadata = match Metadata::from_binary(bytes) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            tracing::debug!("Failed to read metadata sections of {}: {}", name, e);
+            return (BTreeMap::new(), None);
+        }
+    };
+
+    let mut producers = Producers::empty();
+    let mut registry = None;
+    collect(&metadata, &mut producers, &mut registry);
+
+    let mut fields: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    for (field, tools) in producers.iter() {
+        let entry = fields.entry(field.clone()).or_default();
+        for (tool, version) in tools.iter() {
+            entry.insert(tool.clone(), version.clone());
+        }
+    }
+    (fields, registry.map(registry_info))
+}
+
+fn collect<'a>(
+    metadata: &'a Metadata,
+    merged: &mut Producers,
+    registry: &mut Option<&'a RegistryMetadata>,
+) {
+    let (producers, registry_metadata, children) = match metadata {
+        Metadata::Component {
+            producers,
+            registry_metadata,
+            children,
+            ..
+        } => (producers, registry_metadata, children.as_slice()),
+        Metadata::Module {
+            producers,
+            registry_metadata,
+            ..
+        } => (producers, registry_metadata, [].as_slice()),
+    };
+    if let Some(producers) = producers {
+        merged.merge(producers);
+    }
+    if registry.is_none() {
+        *registry = registry_metadata.as_ref();
+    }
+    for child in children {
+        collect(child, merged, registry);
+    }
+}
+
+fn registry_info(registry: &RegistryMetadata) -> RegistryInfo {
+    RegistryInfo {
+        authors: registry.get_authors().cloned().unwrap_or_default(),
+        description: registry.get_description().cloned(),
+        license: registry.get_license().cloned(),
+        links: registry
+            .get_links()
+            .into_iter()
+            .flatten()
+            .map(|link| (link.ty.to_string().to_lowercase(), link.value.clone()))
+            .collect(),
+        categories: registry.get_categories().cloned().unwrap_or_default(),
+    }
+}
+
+/// `tool version` pairs of one producers field, comma separated. Languages usually have no
+/// version.
+pub fn format_tools(tools: &BTreeMap<String, String>) -> String {
+    let tools: Vec<_> = tools
+        .iter()
+        .map(|(tool, version)| format!("{} {}", tool, version).trim_end().to_string())
+        .collect();
+    tools.join(", ")
+}
+
+/// Manifest annotations or labels describing the producers and registry metadata of
+/// `component`, keyed `<prefix>/<component>.producers.<field>`, `<prefix>/<component>.authors`,
+/// `.description`, `.license` and `.links.<type>`
+pub fn metadata_annotations(
+    component: &str,
+    info: &ComponentInfo,
+    output: MetadataOutput,
+) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    for (field, tools) in &info.producers {
+        fields.push((format!("producers.{}", field), format_tools(tools)));
+    }
+    if let Some(registry) = &info.registry {
+        if !registry.authors.is_empty() {
+            fields.push(("authors".to_string(), registry.authors.join(", ")));
+        }
+        if let Some(description) = &registry.description {
+            if output == MetadataOutput::Annotations {
+                fields.push(("description".to_string(), description.clone()));
+            }
+        }
+        if let Some(license) = &registry.license {
+            fields.push(("license".to_string(), license.clone()));
+        }
+        for (ty, url) in &registry.links {
+            fields.push((format!("links.{}", ty), url.clone()));
+        }
+    }
+
+    fields
+        .into_iter()
+        .filter_map(|(field, value)| match output {
+            MetadataOutput::Annotations => Some((format!("{}.{}", component, field), value)),
+            MetadataOutput::Labels => {
+                // Long component names are shortened rather than the field, so keys stay
+                // distinct
+                let component: String = component
+                    .chars()
+                    .take(62usize.saturating_sub(field.len()))
+                    .collect();
+                let value = label_value(&value);
+                (!value.is_empty())
+                    .then(|| (label_value(&format!("{}.{}", component, field)), value))
+            }
+        })
+        .map(|(name, value)| (format!("{}/{}", ANNOTATION_PREFIX, name), value))
+        .collect()
+}
+
+/// Reduces `value` to a valid label value (or key name): at most 63 alphanumerics, `-`, `_`
+/// or `.`, beginning and ending with an alphanumeric
+fn label_value(value: &str) -> String {
+    let replaced: String = value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(63)
+        .collect();
+    replaced
+        .trim_matches(|c: char| !c.is_ascii_alphanumeric())
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info() -> ComponentInfo {
+        let producers = BTreeMap::from([
+            (
+                "language".to_string(),
+                BTreeMap::from([("Rust".to_string(), String::new())]),
+            ),
+            (
+                "processed-by".to_string(),
+                BTreeMap::from([
+                    (
+                        "rustc".to_string(),
+                        "1.82.0 (f6e511eec 2024-10-15)".to_string(),
+                    ),
+                    ("wit-component".to_string(), "0.221.0".to_string()),
+                ]),
+            ),
+        ]);
+        ComponentInfo {
+            imports: Vec::new(),
+            exports: Vec::new(),
+            package: None,
+            claims: None,
+            producers,
+            registry: Some(RegistryInfo {
+                authors: vec!["Ada <ada@example.com>".to_string()],
+                description: Some("Serves the shop API".to_string()),
+                license: Some("Apache-2.0 OR MIT".to_string()),
+                links: BTreeMap::from([(
+                    "repository".to_string(),
+                    "https://github.com/acme/shop".to_string(),
+                )]),
+                categories: Vec::new(),
+            }),
+            version: None,
+        }
+    }
+
+    #[test]
+    fn label_value_replaces_truncates_and_trims() {
+        assert_eq!(
+            label_value("1.82.0 (f6e511eec 2024-10-15)"),
+            "1.82.0__f6e511eec_2024-10-15"
+        );
+        assert_eq!(label_value("héllo"), "h_llo");
+        assert_eq!(label_value("__x--"), "x");
+        assert_eq!(label_value("(--)"), "");
+        assert_eq!(label_value(&"a".repeat(100)), "a".repeat(63));
+        // Truncating can leave a trailing separator, which is trimmed too
+        assert_eq!(
+            label_value(&format!("{} b", "a".repeat(62))),
+            "a".repeat(62)
+        );
+    }
+
+    #[test]
+    fn annotations_keep_values_as_is() {
+        let annotations = metadata_annotations("api", &info(), MetadataOutput::Annotations);
+        let key = |field: &str| format!("{}/api.{}", ANNOTATION_PREFIX, field);
+        assert_eq!(
+            annotations,
+            [
+                (key("producers.language"), "Rust".to_string()),
+                (
+                    key("producers.processed-by"),
+                    "rustc 1.82.0 (f6e511eec 2024-10-15), wit-component 0.221.0".to_string()
+                ),
+                (key("authors"), "Ada <ada@example.com>".to_string()),
+                (key("description"), "Serves the shop API".to_string()),
+                (key("license"), "Apache-2.0 OR MIT".to_string()),
+                (
+                    key("links.repository"),
+                    "https://github.com/acme/shop".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn labels_are_reduced_and_truncated() {
+        let component = "c".repeat(60);
+        let labels = metadata_annotations(&component, &info(), MetadataOutput::Labels);
+        let values: Vec<_> = labels.iter().map(|(_, value)| value.as_str()).collect();
+        assert_eq!(
+            values,
+            [
+                "Rust",
+                "rustc_1.82.0__f6e511eec_2024-10-15___wit-component_0.221.0",
+                "Ada__ada_example.com",
+                "Apache-2.0_OR_MIT",
+                "https___github.com_acme_shop",
+            ]
+        );
+        let names: Vec<_> = labels
+            .iter()
+            .map(|(key, _)| {
+                key.strip_prefix(&format!("{}/", ANNOTATION_PREFIX))
+                    .unwrap()
+            })
+            .collect();
+        let fields = [
+            "producers.language",
+            "producers.processed-by",
+            "authors",
+            "license",
+            "links.repository",
+        ];
+        for (name, field) in names.iter().zip(fields) {
+            assert!(name.len() <= 63, "{}", name);
+            assert!(name.starts_with("ccc") && name.ends_with(&format!("c.{}", field)));
+        }
+    }
+}
@@ -1,5 +1,37 @@
+mod cache;
+pub use cache::{file_stamp, ComponentCache, FileStamp};
+mod claims;
+pub use claims::{claims_annotations, ClaimsPolicy};
 mod constructor;
-pub use constructor::{ConstructorManifest, RUNTIME_INTERFACES};
+pub use constructor::{
+    AliasRules, ConstructorManifest, ImportPolicy, LinkConstructor, ValidationError,
+    ValidationReport, DEFAULT_LINK_NAME, RUNTIME_INTERFACES,
+};
 mod decode;
-pub use decode::process_wasm_file;
+mod extract;
+mod fetch;
+mod lint;
+mod metadata;
+#[cfg(feature = "native")]
+mod nats;
+pub use decode::{decode_wasm_bytes, process_wasm_bytes, process_wasm_file, process_wit_dir};
+pub use lint::{run_lints, LintConfig, Severity, RULES as LINT_RULES};
+pub use metadata::{format_tools, metadata_annotations, MetadataOutput, ANNOTATION_PREFIX};
+mod project;
+pub use project::WashProject;
+mod providers;
+pub use providers::{
+    is_provider_archive, lookup_provider, lookup_provider_archive, lookup_provider_by_name,
+    providers_exporting,
+};
 mod resolver;
+mod shared;
+pub use shared::{registry_manifest_paths, shared_reference, SharedApplications};
+mod stub;
+pub use stub::{generate_stub, StubMode};
+#[cfg(feature = "native")]
+mod wadm;
+#[cfg(feature = "native")]
+pub use wadm::WadmClient;
+mod wasm_pkg;
+pub use wasm_pkg::ImageRef;
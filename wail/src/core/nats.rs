@@ -0,0 +1,160 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context};
+use serde_json::json;
+
+/// A minimal client for the core NATS protocol, enough for request/reply against the wadm
+/// API. Supports token and user/password authentication in the URL, but not TLS or
+/// credentials files.
+pub struct NatsClient {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    /// Subject prefix replies are received on
+    inbox: String,
+    requests: u64,
+    timeout: Duration,
+}
+
+impl NatsClient {
+    /// Connects to `url` (`nats://[token@|user:password@]host[:port]`)
+    pub fn connect(url: &str, timeout: Duration) -> anyhow::Result<Self> {
+        let address = url
+            .strip_prefix("nats://")
+            .or_else(|| url.strip_prefix("tcp://"))
+            .unwrap_or(url);
+        let (auth, host) = match address.rsplit_once('@') {
+            Some((auth, host)) => (Some(auth), host),
+            None => (None, address),
+        };
+        let host = if host.contains(':') {
+            host.to_string()
+        } else {
+            format!("{}:4222", host)
+        };
+
+        let socket = host
+            .to_socket_addrs()
+            .with_context(|| format!("failed to resolve {}", host))?
+            .next()
+            .with_context(|| format!("{} did not resolve to an address", host))?;
+        let writer = TcpStream::connect_timeout(&socket, timeout)
+            .with_context(|| format!("failed to connect to NATS at {}", host))?;
+        writer.set_read_timeout(Some(timeout))?;
+        let mut client = Self {
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+            inbox: String::new(),
+            requests: 0,
+            timeout,
+        };
+
+        let info = client.read_line()?;
+        let Some(info) = info.strip_prefix("INFO ") else {
+            bail!("{} is not a NATS server, it sent {}", host, info);
+        };
+        let info: serde_json::Value = serde_json::from_str(info)?;
+        if info["tls_required"] == true {
+            bail!("NATS server {} requires TLS, which is not supported", host);
+        }
+
+        let mut connect = json!({
+            "verbose": false,
+            "pedantic": false,
+            "lang": "rust",
+            "name": "wail",
+            "version": env!("CARGO_PKG_VERSION"),
+            "protocol": 1,
+        });
+        match auth.map(|auth| auth.split_once(':')) {
+            Some(Some((user, password))) => {
+                connect["user"] = json!(user);
+                connect["pass"] = json!(password);
+            }
+            Some(None) => connect["auth_token"] = json!(auth),
+            None => {}
+        }
+        client.write(format!("CONNECT {}\r\nPING\r\n", connect).as_bytes())?;
+        loop {
+            let line = client.read_line()?;
+            if line == "PONG" {
+                break;
+            }
+            if let Some(error) = line.strip_prefix("-ERR ") {
+                bail!("NATS server {} refused the connection: {}", host, error);
+            }
+        }
+
+        let nonce = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or_default();
+        client.inbox = format!("_INBOX.wail.{}{}", std::process::id(), nonce);
+        client.write(format!("SUB {}.* 1\r\n", client.inbox).as_bytes())?;
+        Ok(client)
+    }
+
+    /// Publishes `payload` to `subject` and waits for the first reply
+    pub fn request(&mut self, subject: &str, payload: &[u8]) -> anyhow::Result<Vec<u8>> {
+        self.requests += 1;
+        let reply = format!("{}.{}", self.inbox, self.requests);
+        let mut message = format!("PUB {} {} {}\r\n", subject, reply, payload.len()).into_bytes();
+        message.extend_from_slice(payload);
+        message.extend_from_slice(b"\r\n");
+        self.write(&message)?;
+
+        loop {
+            let line = self
+                .read_line()
+                .map_err(|e| match e.downcast_ref::<std::io::Error>() {
+                    Some(io) if is_timeout(io) => anyhow::anyhow!(
+                        "no reply on {} within {:?}, is anything subscribed to it?",
+                        subject,
+                        self.timeout
+                    ),
+                    _ => e,
+                })?;
+            if line == "PING" {
+                self.write(b"PONG\r\n")?;
+            } else if let Some(error) = line.strip_prefix("-ERR ") {
+                bail!("NATS error: {}", error);
+            } else if let Some(header) = line.strip_prefix("MSG ") {
+                // MSG <subject> <sid> [reply-to] <#bytes>
+                let fields: Vec<_> = header.split_whitespace().collect();
+                let length: usize = fields
+                    .last()
+                    .and_then(|length| length.parse().ok())
+                    .with_context(|| format!("malformed NATS message header {}", line))?;
+                let mut body = vec![0; length + 2];
+                self.reader.read_exact(&mut body)?;
+                body.truncate(length);
+                // Replies to earlier, timed out requests are dropped
+                if fields.first() == Some(&reply.as_str()) {
+                    return Ok(body);
+                }
+            }
+        }
+    }
+
+    fn read_line(&mut self) -> anyhow::Result<String> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            bail!("NATS server closed the connection");
+        }
+        Ok(line.trim_end().to_string())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
+        self.writer.write_all(bytes)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+fn is_timeout(error: &std::io::Error) -> bool {
+    matches!(
+        error.kind(),
+        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+    )
+}
@@ -0,0 +1,124 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// The parts of a wash project's `wasmcloud.toml` used to find its build artifact and WIT
+#[derive(Debug, Deserialize)]
+pub struct WashProject {
+    pub name: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(rename = "type", default)]
+    project_type: Option<String>,
+    /// WIT directory, `./wit` by default
+    #[serde(default)]
+    wit: Option<PathBuf>,
+    /// Build directory, `./build` by default
+    #[serde(default)]
+    build: Option<PathBuf>,
+    #[serde(default)]
+    component: Option<WashComponent>,
+    /// Directory holding `wasmcloud.toml`
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct WashComponent {
+    build_artifact: Option<PathBuf>,
+    destination: Option<PathBuf>,
+    wit_world: Option<String>,
+}
+
+impl WashProject {
+    /// Reads `wasmcloud.toml` from the project directory `dir`
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        let manifest = Self::manifest_path(dir);
+        let content = std::fs::read_to_string(&manifest)
+            .map_err(|e| anyhow::anyhow!("failed to read {}: {}", manifest.display(), e))?;
+        let mut project: WashProject = toml::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("failed to parse {}: {}", manifest.display(), e))?;
+        project.dir = dir.to_path_buf();
+        Ok(project)
+    }
+
+    pub fn manifest_path(dir: &Path) -> PathBuf {
+        dir.join("wasmcloud.toml")
+    }
+
+    pub fn is_provider(&self) -> bool {
+        self.project_type.as_deref() == Some("provider")
+    }
+
+    pub fn wit_dir(&self) -> PathBuf {
+        self.dir
+            .join(self.wit.as_deref().unwrap_or(Path::new("wit")))
+    }
+
+    pub fn wit_world(&self) -> Option<&str> {
+        self.component.as_ref()?.wit_world.as_deref()
+    }
+
+    /// The `.wit` files directly in the WIT directory
+    pub fn wit_files(&self) -> Vec<PathBuf> {
+        let Ok(entries) = std::fs::read_dir(self.wit_dir()) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "wit"))
+            .collect()
+    }
+
+    fn build_dir(&self) -> PathBuf {
+        self.dir
+            .join(self.build.as_deref().unwrap_or(Path::new("build")))
+    }
+
+    /// The built artifact: the configured destination or build artifact, else the signed
+    /// component in the build directory, else any component (or provider archive) there
+    pub fn artifact(&self) -> Option<PathBuf> {
+        let build = self.build_dir();
+        if self.is_provider() {
+            return find_in(&build, |name| name.ends_with(".par.gz"));
+        }
+        self.component
+            .iter()
+            .flat_map(|c| [c.destination.as_ref(), c.build_artifact.as_ref()])
+            .flatten()
+            .map(|path| self.dir.join(path))
+            .find(|path| path.is_file())
+            .or_else(|| find_in(&build, |name| name.ends_with("_s.wasm")))
+            .or_else(|| find_in(&build, |name| name.ends_with(".wasm")))
+    }
+
+    /// Where `wash build` writes the artifact of an unbuilt project
+    pub fn expected_artifact(&self) -> PathBuf {
+        let build = self.build_dir();
+        if self.is_provider() {
+            return build.join(format!("{}.par.gz", self.name));
+        }
+        self.component
+            .as_ref()
+            .and_then(|c| c.destination.as_ref())
+            .map(|path| self.dir.join(path))
+            .unwrap_or_else(|| build.join(format!("{}_s.wasm", self.name.replace('-', "_"))))
+    }
+}
+
+/// First file in `dir` (sorted by name) whose name matches
+fn find_in(dir: &Path, matches: impl Fn(&str) -> bool) -> Option<PathBuf> {
+    let mut paths: Vec<_> = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| matches(&name.to_string_lossy()))
+        })
+        .collect();
+    paths.sort();
+    paths.into_iter().next()
+}
@@ -0,0 +1,226 @@
+use std::fs::File;
+use std::io::{self, Read, Seek};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+
+use super::wasm_pkg::ImageRef;
+use crate::models::{ComponentInfo, InterfaceInfo, PackageInfo};
+
+/// A capability provider whose interfaces are known without inspecting its artifact
+pub struct KnownProvider {
+    /// Short provider name, also matched against component names in WADM manifests
+    pub name: &'static str,
+    /// Image name under `ghcr.io/wasmcloud`, matched against OCI references and archive names
+    pub image: &'static str,
+    /// Interfaces the provider calls on linked components
+    pub imports: &'static [(&'static str, &'static str, &'static str)],
+    /// Interfaces the provider offers to linked components
+    pub exports: &'static [(&'static str, &'static str, &'static str)],
+}
+
+/// Catalog of the first-party wasmCloud capability providers
+pub const KNOWN_PROVIDERS: &[KnownProvider] = &[
+    KnownProvider {
+        name: "httpserver",
+        image: "http-server",
+        // HTTP server IMPORTS incoming-handler (which our component exports)
+        imports: &[("wasi", "http", "incoming-handler")],
+        // HTTP server EXPORTS outgoing-handler (which our component imports)
+        exports: &[("wasi", "http", "outgoing-handler")],
+    },
+    KnownProvider {
+        name: "httpclient",
+        image: "http-client",
+        imports: &[],
+        exports: &[("wasi", "http", "outgoing-handler")],
+    },
+    KnownProvider {
+        name: "keyvalue-redis",
+        image: "keyvalue-redis",
+        imports: &[],
+        exports: &[
+            ("wasi", "keyvalue", "store"),
+            ("wasi", "keyvalue", "atomics"),
+        ],
+    },
+    KnownProvider {
+        name: "keyvalue-nats",
+        image: "keyvalue-nats",
+        imports: &[],
+        exports: &[
+            ("wasi", "keyvalue", "store"),
+            ("wasi", "keyvalue", "atomics"),
+            ("wasi", "keyvalue", "batch"),
+        ],
+    },
+    KnownProvider {
+        name: "messaging-nats",
+        image: "messaging-nats",
+        imports: &[("wasmcloud", "messaging", "handler")],
+        exports: &[("wasmcloud", "messaging", "consumer")],
+    },
+    KnownProvider {
+        name: "messaging-kafka",
+        image: "messaging-kafka",
+        imports: &[("wasmcloud", "messaging", "handler")],
+        exports: &[("wasmcloud", "messaging", "consumer")],
+    },
+    KnownProvider {
+        name: "blobstore-fs",
+        image: "blobstore-fs",
+        imports: &[],
+        exports: &[("wasi", "blobstore", "blobstore")],
+    },
+    KnownProvider {
+        name: "blobstore-s3",
+        image: "blobstore-s3",
+        imports: &[],
+        exports: &[("wasi", "blobstore", "blobstore")],
+    },
+    KnownProvider {
+        name: "sqldb-postgres",
+        image: "sqldb-postgres",
+        imports: &[],
+        exports: &[
+            ("wasmcloud", "postgres", "query"),
+            ("wasmcloud", "postgres", "prepared"),
+        ],
+    },
+];
+
+/// Finds the catalog entry of an OCI reference under `ghcr.io/wasmcloud`, or of a `file://`
+/// provider archive
+pub fn lookup_provider(reference: &str) -> Option<&'static KnownProvider> {
+    if let Some(path) = reference.strip_prefix("file://") {
+        return lookup_provider_archive(path);
+    }
+    let image = ImageRef::parse(reference)?;
+    let repository = image.repository.strip_prefix("wasmcloud/")?;
+    KNOWN_PROVIDERS
+        .iter()
+        .find(|provider| provider.image == repository)
+}
+
+/// Finds the catalog entry of a provider archive file or URL named after the image,
+/// optionally followed by a version, like `http-server-0.23.0.par.gz`
+pub fn lookup_provider_archive(path: &str) -> Option<&'static KnownProvider> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let stem = [".par.gz", ".par", ".tar.gz", ".tgz"]
+        .iter()
+        .find_map(|extension| name.strip_suffix(extension))
+        .unwrap_or(name);
+    KNOWN_PROVIDERS.iter().find(|provider| {
+        stem.strip_prefix(provider.image).is_some_and(|version| {
+            version.is_empty()
+                || version
+                    .strip_prefix(['-', '_'])
+                    .is_some_and(|version| version.starts_with(|c: char| c.is_ascii_digit()))
+        })
+    })
+}
+
+/// Finds the catalog entry with the given short name
+pub fn lookup_provider_by_name(name: &str) -> Option<&'static KnownProvider> {
+    KNOWN_PROVIDERS
+        .iter()
+        .find(|provider| provider.name == name)
+}
+
+/// Catalog entries that export `namespace:package/interface`
+pub fn providers_exporting(
+    namespace: &str,
+    package: &str,
+    interface: &str,
+) -> Vec<&'static KnownProvider> {
+    KNOWN_PROVIDERS
+        .iter()
+        .filter(|provider| {
+            provider
+                .exports
+                .iter()
+                .any(|&(ns, pkg, name)| ns == namespace && pkg == package && name == interface)
+        })
+        .collect()
+}
+
+impl KnownProvider {
+    pub fn component_info(&self) -> ComponentInfo {
+        let to_info = |&(namespace, package, name): &(&str, &str, &str)| InterfaceInfo {
+            name: name.to_string(),
+            namespace: namespace.to_string(),
+            package: package.to_string(),
+            version: None,
+            functions: Vec::new(),
+            resources_from: Vec::new(),
+        };
+        ComponentInfo {
+            imports: self.imports.iter().map(to_info).collect(),
+            exports: self.exports.iter().map(to_info).collect(),
+            package: Some(PackageInfo {
+                namespace: "wasmcloud".to_string(),
+                name: self.name.to_string(),
+            }),
+            claims: None,
+            producers: Default::default(),
+            registry: None,
+            version: None,
+        }
+    }
+}
+
+/// Whether `path` is a provider archive: a tarball, usually gzipped and named `.par.gz`,
+/// holding the provider's claims and its per-target binaries
+pub fn is_provider_archive(path: &Path) -> bool {
+    let name = path.to_string_lossy();
+    if name.ends_with(".par.gz") || name.ends_with(".par") {
+        return true;
+    }
+    let Ok(mut file) = File::open(path) else {
+        return false;
+    };
+    let mut magic = [0u8; 2];
+    if file.read_exact(&mut magic).is_err() || file.rewind().is_err() {
+        return false;
+    }
+    let entries = if magic == [0x1f, 0x8b] {
+        tar_entries(GzDecoder::new(file))
+    } else {
+        tar_entries(file)
+    };
+    entries.iter().any(|entry| entry == "claims.jwt")
+        && entries.iter().any(|entry| entry.ends_with(".bin"))
+}
+
+/// Names of the entries of a tar archive, up to the first unreadable header
+fn tar_entries(mut reader: impl Read) -> Vec<String> {
+    const BLOCK: u64 = 512;
+
+    let mut entries = Vec::new();
+    let mut header = [0u8; BLOCK as usize];
+    while reader.read_exact(&mut header).is_ok() && header.iter().any(|&byte| byte != 0) {
+        let field = |range: std::ops::Range<usize>| {
+            let bytes = &header[range];
+            let end = bytes
+                .iter()
+                .position(|&byte| byte == 0)
+                .unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+        };
+        let Ok(size) = u64::from_str_radix(&field(124..136), 8) else {
+            break;
+        };
+        // ustar headers split long names into a prefix and a name
+        let name = match (&header[257..262] == b"ustar", field(345..500)) {
+            (true, prefix) if !prefix.is_empty() => format!("{}/{}", prefix, field(0..100)),
+            _ => field(0..100),
+        };
+        entries.push(name.trim_start_matches("./").to_string());
+
+        let padded = size.div_ceil(BLOCK) * BLOCK;
+        if io::copy(&mut reader.by_ref().take(padded), &mut io::sink()).ok() != Some(padded) {
+            break;
+        }
+    }
+    entries
+}
@@ -0,0 +1,301 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use wadm_types::{Manifest, Properties, SharedApplicationComponentProperties};
+
+use crate::core::{lookup_provider, lookup_provider_by_name, ComponentCache};
+use crate::models::{ComponentInfo, EntityKind};
+
+/// Manifests of shared applications whose components other manifests can reference with
+/// `application:` instead of `image:`
+#[derive(Default)]
+pub struct SharedApplications {
+    /// Manifests with the file they were read from, or `wadm:<lattice>/<name>` for
+    /// applications deployed in a lattice
+    manifests: Vec<(PathBuf, Manifest)>,
+}
+
+impl SharedApplications {
+    /// Loads the given manifest files and every YAML manifest in `registry`
+    pub fn load(manifests: &[PathBuf], registry: Option<&Path>) -> anyhow::Result<Self> {
+        let mut shared = Self::default();
+        for path in manifests {
+            shared.manifests.push((path.clone(), read_manifest(path)?));
+        }
+
+        if let Some(registry) = registry {
+            for path in registry_manifest_paths(registry)? {
+                match read_manifest(&path) {
+                    Ok(manifest) => shared.manifests.push((path, manifest)),
+                    // Registries may hold other YAML files, only manifests matter here
+                    Err(e) => println!("Skipping {} in manifest registry: {:#}", path.display(), e),
+                }
+            }
+        }
+
+        Ok(shared)
+    }
+
+    /// Adds a shared application deployed in `lattice`, unless a manifest file of the same
+    /// name was given
+    #[cfg(feature = "native")]
+    pub fn add_deployed(&mut self, lattice: &str, manifest: Manifest) {
+        if self
+            .manifests
+            .iter()
+            .any(|(_, m)| m.metadata.name == manifest.metadata.name)
+        {
+            return;
+        }
+        let origin = PathBuf::from(format!("wadm:{}/{}", lattice, manifest.metadata.name));
+        self.manifests.push((origin, manifest));
+    }
+
+    /// Resolves the interfaces of the shared component `reference` points at.
+    ///
+    /// Only exports are returned: the imports of a shared component are linked by the
+    /// application that deploys it.
+    pub fn resolve(
+        &self,
+        reference: &SharedApplicationComponentProperties,
+        cache: &mut ComponentCache,
+    ) -> anyhow::Result<(ComponentInfo, EntityKind)> {
+        let (manifest_path, manifest) = self
+            .manifests
+            .iter()
+            .find(|(_, manifest)| manifest.metadata.name == reference.name)
+            .with_context(|| {
+                format!(
+                    "shared application {} not found, pass its manifest with --shared-manifest or --manifest-registry",
+                    reference.name
+                )
+            })?;
+
+        if !manifest.shared() {
+            anyhow::bail!(
+                "application {} ({}) is not shared, annotate it with {}: \"true\"",
+                reference.name,
+                manifest_path.display(),
+                wadm_types::SHARED_ANNOTATION_KEY
+            );
+        }
+
+        let component = manifest
+            .components()
+            .find(|c| c.name == reference.component)
+            .with_context(|| {
+                format!(
+                    "shared application {} has no component named {}",
+                    reference.name, reference.component
+                )
+            })?;
+
+        let (image, kind) = match &component.properties {
+            Properties::Component { properties } => {
+                (properties.image.as_deref(), EntityKind::Component)
+            }
+            Properties::Capability { properties } => {
+                (properties.image.as_deref(), EntityKind::Capability)
+            }
+        };
+        let Some(image) = image else {
+            anyhow::bail!(
+                "component {} of shared application {} has no image",
+                reference.component,
+                reference.name
+            );
+        };
+
+        let info = match kind {
+            EntityKind::Capability => lookup_provider_by_name(&component.name)
+                .or_else(|| lookup_provider(image))
+                .map(|provider| provider.component_info()),
+            EntityKind::Component if image.starts_with("file://") => {
+                // File images are relative to the shared manifest
+                let path = manifest_path
+                    .parent()
+                    .unwrap_or(Path::new("."))
+                    .join(image.trim_start_matches("file://"));
+                Some(cache.process_wasm_file(&component.name, &path)?)
+            }
+            EntityKind::Component => {
+                lookup_provider(image).map(|provider| provider.component_info())
+            }
+        };
+
+        let exports = match info {
+            Some(info) => info.exports,
+            None => {
+                println!(
+                    "No interface information known for {}/{}, adding it without links",
+                    reference.name, reference.component
+                );
+                Vec::new()
+            }
+        };
+
+        Ok((
+            ComponentInfo {
+                imports: Vec::new(),
+                exports,
+                package: None,
+                claims: None,
+                producers: Default::default(),
+                registry: None,
+                version: None,
+            },
+            kind,
+        ))
+    }
+}
+
+/// The `application:` reference of a WADM component, if it points at a shared application
+pub fn shared_reference(properties: &Properties) -> Option<&SharedApplicationComponentProperties> {
+    match properties {
+        Properties::Component { properties } => properties.application.as_ref(),
+        Properties::Capability { properties } => properties.application.as_ref(),
+    }
+}
+
+/// YAML files in a manifest registry directory, in a stable order
+pub fn registry_manifest_paths(registry: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(registry)
+        .with_context(|| format!("failed to read manifest registry {}", registry.display()))?
+    {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|ext| ext == "yaml" || ext == "yml")
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+fn read_manifest(path: &Path) -> anyhow::Result<Manifest> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    serde_yaml::from_str(&content).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHARED: &str = r#"
+apiVersion: core.oam.dev/v1beta1
+kind: Application
+metadata:
+  name: platform
+  annotations:
+    version: v0.1.0
+    {shared}: "true"
+spec:
+  components:
+    - name: httpclient
+      type: capability
+      properties:
+        image: ghcr.io/wasmcloud/http-client:0.12.1
+    - name: custom
+      type: component
+      properties:
+        image: ghcr.io/acme/custom:1.0.0
+"#;
+
+    const PRIVATE: &str = r#"
+apiVersion: core.oam.dev/v1beta1
+kind: Application
+metadata:
+  name: private
+spec:
+  components:
+    - name: app
+      type: component
+      properties:
+        image: ghcr.io/acme/app:1.0.0
+"#;
+
+    /// A manifest registry holding the shared and private applications and a non-manifest
+    fn registry(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wail-shared-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let platform = SHARED.replace("{shared}", wadm_types::SHARED_ANNOTATION_KEY);
+        std::fs::write(dir.join("platform.yaml"), platform).unwrap();
+        std::fs::write(dir.join("private.yml"), PRIVATE).unwrap();
+        std::fs::write(dir.join("values.yaml"), "replicas: 3\n").unwrap();
+        std::fs::write(dir.join("README.md"), "# Manifests\n").unwrap();
+        dir
+    }
+
+    fn reference(name: &str, component: &str) -> SharedApplicationComponentProperties {
+        SharedApplicationComponentProperties {
+            name: name.to_string(),
+            component: component.to_string(),
+        }
+    }
+
+    fn resolve(
+        shared: &SharedApplications,
+        name: &str,
+        component: &str,
+    ) -> anyhow::Result<(ComponentInfo, EntityKind)> {
+        shared.resolve(&reference(name, component), &mut ComponentCache::new())
+    }
+
+    #[test]
+    fn registry_loads_manifests_only() {
+        let dir = registry("load");
+        let paths = registry_manifest_paths(&dir).unwrap();
+        assert_eq!(paths.len(), 3);
+        let shared = SharedApplications::load(&[], Some(&dir)).unwrap();
+        let names: Vec<_> = shared
+            .manifests
+            .iter()
+            .map(|(_, manifest)| manifest.metadata.name.as_str())
+            .collect();
+        assert_eq!(names, ["platform", "private"]);
+    }
+
+    #[test]
+    fn explicit_manifests_must_parse() {
+        let dir = registry("explicit");
+        assert!(SharedApplications::load(&[dir.join("values.yaml")], None).is_err());
+        assert!(SharedApplications::load(&[dir.join("missing.yaml")], None).is_err());
+    }
+
+    #[test]
+    fn shared_provider_exports_come_from_the_catalog() {
+        let shared = SharedApplications::load(&[], Some(&registry("provider"))).unwrap();
+        let (info, kind) = resolve(&shared, "platform", "httpclient").unwrap();
+        assert_eq!(kind, EntityKind::Capability);
+        assert!(info.imports.is_empty());
+        assert_eq!(info.exports.len(), 1);
+        assert_eq!(info.exports[0].name, "outgoing-handler");
+
+        // Unknown images are added without interfaces
+        let (info, kind) = resolve(&shared, "platform", "custom").unwrap();
+        assert_eq!(kind, EntityKind::Component);
+        assert!(info.exports.is_empty());
+    }
+
+    #[test]
+    fn resolution_errors() {
+        let shared = SharedApplications::load(&[], Some(&registry("errors"))).unwrap();
+        let error = |name, component| resolve(&shared, name, component).unwrap_err().to_string();
+        assert!(error("missing", "app").starts_with("shared application missing not found"));
+        assert!(error("private", "app").starts_with("application private ("));
+        let not_shared = format!(
+            "is not shared, annotate it with {}: \"true\"",
+            wadm_types::SHARED_ANNOTATION_KEY
+        );
+        assert!(error("private", "app").ends_with(&not_shared));
+        assert_eq!(
+            error("platform", "nope"),
+            "shared application platform has no component named nope"
+        );
+    }
+}
@@ -0,0 +1,338 @@
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use wasm_encoder::{
+    BlockType, CodeSection, ConstExpr, CustomSection, ExportKind, ExportSection, Function,
+    FunctionSection, GlobalSection, GlobalType, Instruction, MemorySection, MemoryType, Module,
+    NameMap, NameSection, TypeSection, ValType,
+};
+use wit_component::{ComponentEncoder, DecodedWasm, StringEncoding};
+use wit_parser::abi::{AbiVariant, WasmType};
+use wit_parser::{Mangling, PackageName, Resolve, WasmExport, WorldItem};
+
+/// Offset of the zeroed scratch area handed out as the return pointer for default values
+const RETURN_AREA: i32 = 8;
+/// First address handed out by the bump allocator behind `cabi_realloc`
+const HEAP_START: i32 = 8192;
+
+/// What a generated stub does when one of its functions is called. Stubs stand in for
+/// missing providers while testing wiring, so neither mode returns configurable values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum StubMode {
+    /// Trap with a descriptive function name
+    Trap,
+    /// Return the all-zero value of the result type (0, false, "", [], none, first case).
+    /// Functions returning resource handles still trap.
+    Default,
+}
+
+/// Synthesizes a component that exports `namespace:package/<interface>@version` for each of
+/// `interfaces`. wasmCloud links whole packages, so one stub serves every interface of a
+/// package.
+///
+/// Each interface definition is taken from the decoded WIT of the component path paired
+/// with it, a component that imports it. Functions returning resource handles cannot be
+/// given a default value and will still trap when their result is lifted.
+pub fn generate_stub(
+    namespace: &str,
+    package: &str,
+    version: Option<&str>,
+    interfaces: &[(String, PathBuf)],
+    mode: StubMode,
+) -> anyhow::Result<Vec<u8>> {
+    // The WIT of every importer is merged, so interfaces only some of them use are known
+    let mut resolve = Resolve::default();
+    let mut merged: Vec<&Path> = Vec::new();
+    for (_, importer_path) in interfaces {
+        if merged.contains(&importer_path.as_path()) {
+            continue;
+        }
+        let bytes = std::fs::read(importer_path)
+            .with_context(|| format!("failed to read {}", importer_path.display()))?;
+        match wit_component::decode(&bytes)? {
+            DecodedWasm::Component(mut decoded, world) => {
+                // Every decoded component has a `root:component` package describing its own
+                // world, which would conflict between importers
+                if let Some(pkg) = decoded.worlds[world].package {
+                    let renamed = PackageName {
+                        namespace: "wail".to_string(),
+                        name: format!("importer{}", merged.len()),
+                        version: None,
+                    };
+                    let original =
+                        std::mem::replace(&mut decoded.packages[pkg].name, renamed.clone());
+                    decoded.package_names.shift_remove(&original);
+                    decoded.package_names.insert(renamed, pkg);
+                }
+                resolve.merge(decoded).with_context(|| {
+                    format!(
+                        "the WIT of {} conflicts with the other importers of {}:{}",
+                        importer_path.display(),
+                        namespace,
+                        package
+                    )
+                })?;
+            }
+            DecodedWasm::WitPackage(..) => {
+                anyhow::bail!(
+                    "{} is a WIT package, not a component",
+                    importer_path.display()
+                )
+            }
+        }
+        merged.push(importer_path);
+    }
+
+    let mut exports = Vec::new();
+    for (interface, importer_path) in interfaces {
+        let interface_id = resolve
+            .interfaces
+            .iter()
+            .find(|(_, def)| {
+                def.name.as_deref() == Some(interface.as_str())
+                    && def.package.is_some_and(|pkg| {
+                        let name = &resolve.packages[pkg].name;
+                        name.namespace == namespace
+                            && name.name == package
+                            && name.version.as_ref().map(|v| v.to_string()).as_deref() == version
+                    })
+            })
+            .map(|(id, _)| id)
+            .with_context(|| {
+                format!(
+                    "interface {}:{}/{}{} not found in {}",
+                    namespace,
+                    package,
+                    interface,
+                    version.map(|v| format!("@{}", v)).unwrap_or_default(),
+                    importer_path.display()
+                )
+            })?;
+        let qualified = resolve
+            .id_of(interface_id)
+            .context("stubbed interface has no package")?;
+        exports.push(format!("  export {};\n", qualified));
+    }
+
+    // Let wit-parser elaborate the world so type dependencies become imports
+    let stub_pkg = resolve.push_str(
+        "wail-stub.wit",
+        &format!(
+            "package wail:stub;\n\nworld stub {{\n{}}}\n",
+            exports.concat()
+        ),
+    )?;
+    let world_id = resolve.select_world(stub_pkg, Some("stub"))?;
+
+    let mut module = stub_module(&resolve, world_id, mode)?;
+    wit_component::embed_component_metadata(&mut module, &resolve, world_id, StringEncoding::UTF8)?;
+
+    ComponentEncoder::default()
+        .module(&module)?
+        .validate(true)
+        .encode()
+}
+
+fn stub_module(
+    resolve: &Resolve,
+    world_id: wit_parser::WorldId,
+    mode: StubMode,
+) -> anyhow::Result<Vec<u8>> {
+    let mut types = TypeSection::new();
+    let mut functions = FunctionSection::new();
+    let mut code = CodeSection::new();
+    let mut exports = ExportSection::new();
+    let mut names = NameMap::new();
+
+    // Index 0 is cabi_realloc, a bump allocator for arguments lowered into the stub
+    types.ty().function(
+        [ValType::I32, ValType::I32, ValType::I32, ValType::I32],
+        [ValType::I32],
+    );
+    functions.function(0);
+    code.function(&realloc_body());
+    exports.export("cabi_realloc", ExportKind::Func, 0);
+    names.append(0, "cabi_realloc");
+
+    let world = &resolve.worlds[world_id];
+    let mut index = 1;
+    for (key, item) in &world.exports {
+        let WorldItem::Interface { id, .. } = item else {
+            continue;
+        };
+        let qualified = resolve.name_world_key(key);
+        for func in resolve.interfaces[*id].functions.values() {
+            let sig = resolve.wasm_signature(AbiVariant::GuestExport, func);
+            let params: Vec<ValType> = sig.params.iter().map(core_type).collect();
+            let results: Vec<ValType> = sig.results.iter().map(core_type).collect();
+
+            let mut body = Function::new([]);
+            match mode {
+                StubMode::Trap => {
+                    body.instruction(&Instruction::Unreachable);
+                }
+                StubMode::Default if sig.retptr => {
+                    body.instruction(&Instruction::I32Const(RETURN_AREA));
+                }
+                StubMode::Default => {
+                    for result in &results {
+                        body.instruction(&zero(*result));
+                    }
+                }
+            }
+            body.instruction(&Instruction::End);
+
+            types.ty().function(params, results);
+            functions.function(index);
+            code.function(&body);
+
+            let export_name = resolve.wasm_export_name(
+                Mangling::Legacy,
+                WasmExport::Func {
+                    interface: Some(key),
+                    func,
+                    post_return: false,
+                },
+            );
+            exports.export(&export_name, ExportKind::Func, index);
+            // Runtimes print this name in the backtrace of a trapping stub
+            names.append(
+                index,
+                &format!("wail stub: {}#{} is not implemented", qualified, func.name),
+            );
+            index += 1;
+        }
+    }
+    exports.export("memory", ExportKind::Memory, 0);
+
+    let mut memories = MemorySection::new();
+    memories.memory(MemoryType {
+        minimum: 1,
+        maximum: None,
+        memory64: false,
+        shared: false,
+        page_size_log2: None,
+    });
+
+    let mut globals = GlobalSection::new();
+    globals.global(
+        GlobalType {
+            val_type: ValType::I32,
+            mutable: true,
+            shared: false,
+        },
+        &ConstExpr::i32_const(HEAP_START),
+    );
+
+    let mut name_section = NameSection::new();
+    name_section.functions(&names);
+
+    let mut module = Module::new();
+    module
+        .section(&types)
+        .section(&functions)
+        .section(&memories)
+        .section(&globals)
+        .section(&exports)
+        .section(&code)
+        .section(&name_section)
+        .section(&CustomSection {
+            name: Cow::Borrowed("wail-stub"),
+            data: Cow::Owned(
+                format!(
+                    "stub for {} generated by wail ({:?} mode)",
+                    world
+                        .exports
+                        .keys()
+                        .map(|key| resolve.name_world_key(key))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    mode
+                )
+                .into_bytes(),
+            ),
+        });
+    Ok(module.finish())
+}
+
+/// `cabi_realloc(old_ptr, old_size, align, new_size) -> ptr` backed by a bump allocator
+fn realloc_body() -> Function {
+    const OLD_PTR: u32 = 0;
+    const OLD_SIZE: u32 = 1;
+    const ALIGN: u32 = 2;
+    const NEW_SIZE: u32 = 3;
+    const PTR: u32 = 4;
+    const END: u32 = 5;
+
+    let mut f = Function::new([(2, ValType::I32)]);
+    // ptr = (heap + align - 1) & -align
+    f.instruction(&Instruction::GlobalGet(0))
+        .instruction(&Instruction::LocalGet(ALIGN))
+        .instruction(&Instruction::I32Add)
+        .instruction(&Instruction::I32Const(1))
+        .instruction(&Instruction::I32Sub)
+        .instruction(&Instruction::I32Const(0))
+        .instruction(&Instruction::LocalGet(ALIGN))
+        .instruction(&Instruction::I32Sub)
+        .instruction(&Instruction::I32And)
+        .instruction(&Instruction::LocalTee(PTR))
+        // end = ptr + new_size
+        .instruction(&Instruction::LocalGet(NEW_SIZE))
+        .instruction(&Instruction::I32Add)
+        .instruction(&Instruction::LocalTee(END))
+        // grow memory when end is past the current size
+        .instruction(&Instruction::MemorySize(0))
+        .instruction(&Instruction::I32Const(16))
+        .instruction(&Instruction::I32Shl)
+        .instruction(&Instruction::I32GtU)
+        .instruction(&Instruction::If(BlockType::Empty))
+        .instruction(&Instruction::LocalGet(END))
+        .instruction(&Instruction::I32Const(16))
+        .instruction(&Instruction::I32ShrU)
+        .instruction(&Instruction::I32Const(1))
+        .instruction(&Instruction::I32Add)
+        .instruction(&Instruction::MemorySize(0))
+        .instruction(&Instruction::I32Sub)
+        .instruction(&Instruction::MemoryGrow(0))
+        .instruction(&Instruction::I32Const(-1))
+        .instruction(&Instruction::I32Eq)
+        .instruction(&Instruction::If(BlockType::Empty))
+        .instruction(&Instruction::Unreachable)
+        .instruction(&Instruction::End)
+        .instruction(&Instruction::End)
+        // carry over the previous allocation when resizing
+        .instruction(&Instruction::LocalGet(OLD_PTR))
+        .instruction(&Instruction::If(BlockType::Empty))
+        .instruction(&Instruction::LocalGet(PTR))
+        .instruction(&Instruction::LocalGet(OLD_PTR))
+        .instruction(&Instruction::LocalGet(OLD_SIZE))
+        .instruction(&Instruction::MemoryCopy {
+            src_mem: 0,
+            dst_mem: 0,
+        })
+        .instruction(&Instruction::End)
+        .instruction(&Instruction::LocalGet(END))
+        .instruction(&Instruction::GlobalSet(0))
+        .instruction(&Instruction::LocalGet(PTR))
+        .instruction(&Instruction::End);
+    f
+}
+
+fn core_type(ty: &WasmType) -> ValType {
+    match ty {
+        WasmType::I32 | WasmType::Pointer | WasmType::Length => ValType::I32,
+        WasmType::I64 | WasmType::PointerOrI64 => ValType::I64,
+        WasmType::F32 => ValType::F32,
+        WasmType::F64 => ValType::F64,
+    }
+}
+
+fn zero(ty: ValType) -> Instruction<'static> {
+    match ty {
+        ValType::I64 => Instruction::I64Const(0),
+        ValType::F32 => Instruction::F32Const(0.0),
+        ValType::F64 => Instruction::F64Const(0.0),
+        _ => Instruction::I32Const(0),
+    }
+}
@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use serde_json::{json, Value};
+use wadm_types::Manifest;
+
+use super::nats::NatsClient;
+
+/// Client for the wadm model API of one lattice, served on `wadm.api.<lattice>.model.*`
+pub struct WadmClient {
+    nats: NatsClient,
+    lattice: String,
+}
+
+/// An application stored in wadm
+#[derive(Debug)]
+pub struct ModelSummary {
+    pub name: String,
+    pub deployed_version: Option<String>,
+}
+
+impl WadmClient {
+    pub fn connect(url: &str, lattice: &str, timeout: Duration) -> anyhow::Result<Self> {
+        Ok(Self {
+            nats: NatsClient::connect(url, timeout)?,
+            lattice: lattice.to_string(),
+        })
+    }
+
+    pub fn lattice(&self) -> &str {
+        &self.lattice
+    }
+
+    /// Sends `body` to `wadm.api.<lattice>.<operation>` and returns the parsed response
+    fn call(&mut self, operation: &str, body: &[u8]) -> anyhow::Result<Value> {
+        let subject = format!("wadm.api.{}.{}", self.lattice, operation);
+        let response = self.nats.request(&subject, body)?;
+        serde_json::from_slice(&response)
+            .with_context(|| format!("wadm sent an invalid response to {}", subject))
+    }
+
+    pub fn list(&mut self) -> anyhow::Result<Vec<ModelSummary>> {
+        let response = self.call("model.list", b"")?;
+        // wadm before 0.12 answers with a bare array
+        let models = match &response {
+            Value::Array(models) => models,
+            _ => {
+                check_result(&response, &["success"])?;
+                response["models"]
+                    .as_array()
+                    .context("wadm list response has no models")?
+            }
+        };
+        Ok(models
+            .iter()
+            .filter_map(|model| {
+                Some(ModelSummary {
+                    name: model["name"].as_str()?.to_string(),
+                    deployed_version: model["deployed_version"].as_str().map(str::to_string),
+                })
+            })
+            .collect())
+    }
+
+    /// The manifest of `name` at `version`, or `None` if wadm does not know it
+    pub fn get(&mut self, name: &str, version: &str) -> anyhow::Result<Option<Manifest>> {
+        let body = json!({ "version": version }).to_string();
+        let response = self.call(&format!("model.get.{}", name), body.as_bytes())?;
+        if response["result"] == "not_found" {
+            return Ok(None);
+        }
+        check_result(&response, &["success"])?;
+        let manifest = serde_json::from_value(response["manifest"].clone())
+            .with_context(|| format!("wadm returned an invalid manifest for {}", name))?;
+        Ok(Some(manifest))
+    }
+
+    /// The manifest of the version of `name` that is currently deployed
+    pub fn deployed(&mut self, name: &str) -> anyhow::Result<Option<Manifest>> {
+        let deployed = self
+            .list()?
+            .into_iter()
+            .find(|model| model.name == name)
+            .and_then(|model| model.deployed_version);
+        match deployed {
+            Some(version) => self.get(name, &version),
+            None => Ok(None),
+        }
+    }
+
+    /// Deployed applications annotated as shared
+    pub fn shared_manifests(&mut self) -> anyhow::Result<Vec<Manifest>> {
+        let mut shared = Vec::new();
+        for model in self.list()? {
+            let Some(version) = &model.deployed_version else {
+                continue;
+            };
+            if let Some(manifest) = self.get(&model.name, version)? {
+                if manifest.shared() {
+                    shared.push(manifest);
+                }
+            }
+        }
+        Ok(shared)
+    }
+
+    /// Stores `manifest` as a new version and returns the version wadm recorded
+    pub fn put(&mut self, manifest: &Manifest) -> anyhow::Result<String> {
+        let body = serde_json::to_vec(manifest)?;
+        let response = self.call("model.put", &body)?;
+        check_result(&response, &["created", "newversion"])?;
+        Ok(response["current_version"]
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| manifest.version().to_string()))
+    }
+
+    pub fn deploy(&mut self, name: &str, version: &str) -> anyhow::Result<()> {
+        let body = json!({ "version": version }).to_string();
+        let response = self.call(&format!("model.deploy.{}", name), body.as_bytes())?;
+        check_result(&response, &["acknowledged"])
+    }
+}
+
+/// Fails with wadm's message unless the response's `result` is one of `expected`
+fn check_result(response: &Value, expected: &[&str]) -> anyhow::Result<()> {
+    let result = response["result"].as_str().unwrap_or_default();
+    if expected.contains(&result) {
+        return Ok(());
+    }
+    let message = response["message"].as_str().unwrap_or_default();
+    bail!("wadm answered {}: {}", result, message)
+}
@@ -0,0 +1,745 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::fetch::{self, Response};
+use crate::models::{EntityKind, PackageRef};
+
+/// Registry wkg uses for the `wasi` namespace when the config does not map it
+const WASI_REGISTRY: &str = "wasi.dev";
+
+const OCI_MANIFEST: &str = "application/vnd.oci.image.manifest.v1+json";
+const WASM_LAYER: &str = "application/wasm";
+/// Config and layer media types of components, including those pushed by older wash versions
+const COMPONENT_MEDIA_TYPES: &[&str] = &[
+    WASM_LAYER,
+    "application/vnd.wasm.config.v0+json",
+    "application/vnd.wasmcloud.actor.archive.config",
+    "application/vnd.module.wasm.content.layer.v1+wasm",
+];
+/// Prefix of the config and layer media types of provider archives pushed by wash
+const PROVIDER_MEDIA_TYPE: &str = "application/vnd.wasmcloud.provider.archive.";
+
+/// The parts of the wasm-pkg config (`wkg config`) used to find a package's registry
+#[derive(Debug, Default, Deserialize)]
+pub struct WasmPkgConfig {
+    default_registry: Option<String>,
+    #[serde(default)]
+    namespace_registries: HashMap<String, RegistryMapping>,
+    #[serde(default)]
+    package_registry_overrides: HashMap<String, RegistryMapping>,
+    #[serde(default)]
+    registry: HashMap<String, RegistryConfig>,
+}
+
+/// A registry name, or a registry with inline metadata that skips discovery
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RegistryMapping {
+    Registry(String),
+    Custom {
+        registry: String,
+        metadata: RegistryMetadata,
+    },
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RegistryConfig {
+    oci: Option<OciConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OciConfig {
+    auth: Option<OciAuth>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OciAuth {
+    username: String,
+    password: String,
+}
+
+/// `/.well-known/wasm-pkg/registry.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RegistryMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    preferred_protocol: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    oci: Option<OciMetadata>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    warg: Option<Value>,
+    /// Pre-0.5 form of `oci.registry`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    oci_registry: Option<String>,
+    /// Pre-0.5 form of `oci.namespacePrefix`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    oci_namespace_prefix: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OciMetadata {
+    registry: Option<String>,
+    namespace_prefix: Option<String>,
+}
+
+/// An OCI image reference, `[registry/]repository[:tag][@digest]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageRef {
+    pub registry: String,
+    pub repository: String,
+    /// The digest when the reference pins one, otherwise the tag
+    pub reference: String,
+}
+
+impl ImageRef {
+    /// Parses an image reference with an optional `oci://` prefix. Like other OCI clients,
+    /// a first path segment only names a registry if it looks like a host.
+    pub fn parse(image: &str) -> Option<Self> {
+        let image = image.strip_prefix("oci://").unwrap_or(image);
+        let (name, digest) = match image.split_once('@') {
+            Some((name, digest)) => (name, Some(digest)),
+            None => (image, None),
+        };
+        // A colon after the last slash starts the tag, an earlier one is a registry port
+        let (name, tag) = match name.rsplit_once(':') {
+            Some((name, tag)) if !tag.contains('/') => (name, Some(tag)),
+            _ => (name, None),
+        };
+        let (registry, repository) = match name.split_once('/') {
+            Some((host, path)) if host.contains(['.', ':']) || host == "localhost" => (host, path),
+            _ => ("docker.io", name),
+        };
+        if repository.is_empty() || digest.or(tag).is_some_and(str::is_empty) {
+            return None;
+        }
+        Some(Self {
+            registry: registry.to_string(),
+            repository: repository.to_string(),
+            reference: digest.or(tag).unwrap_or("latest").to_string(),
+        })
+    }
+
+    /// The digest the reference pins, if any
+    pub fn digest(&self) -> Option<&str> {
+        self.reference
+            .contains(':')
+            .then_some(self.reference.as_str())
+    }
+}
+
+impl fmt::Display for ImageRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let separator = if self.reference.contains(':') {
+            '@'
+        } else {
+            ':'
+        };
+        write!(
+            f,
+            "{}/{}{}{}",
+            self.registry, self.repository, separator, self.reference
+        )
+    }
+}
+
+/// A package pulled from its registry
+pub struct PulledPackage {
+    /// Local copy of the component or WIT package
+    pub path: PathBuf,
+    /// OCI reference the package was pulled from, usable as a manifest image
+    pub image: String,
+}
+
+impl WasmPkgConfig {
+    /// Loads `path`, or else the file named by `WKG_CONFIG_FILE`, or else wkg's default
+    /// config. A missing default config is the same as an empty one.
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        let explicit = path
+            .map(Path::to_path_buf)
+            .or_else(|| std::env::var_os("WKG_CONFIG_FILE").map(PathBuf::from));
+        let path = match explicit {
+            Some(path) => path,
+            None => match default_config_path().filter(|path| path.is_file()) {
+                Some(path) => path,
+                None => return Ok(Self::default()),
+            },
+        };
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read wasm-pkg config {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("failed to parse wasm-pkg config {}", path.display()))
+    }
+
+    /// The registry serving `package`, with its metadata if the config gives it inline
+    fn registry_for(
+        &self,
+        package: &PackageRef,
+    ) -> anyhow::Result<(String, Option<RegistryMetadata>)> {
+        let mapping = self
+            .package_registry_overrides
+            .get(&package.package())
+            .or_else(|| self.namespace_registries.get(&package.namespace));
+        Ok(match mapping {
+            Some(RegistryMapping::Registry(registry)) => (registry.clone(), None),
+            Some(RegistryMapping::Custom { registry, metadata }) => {
+                (registry.clone(), Some(metadata.clone()))
+            }
+            None => match &self.default_registry {
+                Some(registry) => (registry.clone(), None),
+                None if package.namespace == "wasi" => (WASI_REGISTRY.to_string(), None),
+                None => bail!(
+                    "no registry for namespace {}, map it in the wasm-pkg config with `wkg config` or set default_registry",
+                    package.namespace
+                ),
+            },
+        })
+    }
+
+    /// Pulls `package` into `dir`, reusing an earlier pull of the same version from the same
+    /// registry. Registry metadata is cached in `dir` too, so cached packages resolve offline.
+    pub fn pull(&self, package: &PackageRef, dir: &Path) -> anyhow::Result<PulledPackage> {
+        let (registry, metadata) = self.registry_for(package)?;
+        let stem = format!("{}@{}", package.name, package.version);
+        let package_dir = dir.join(&registry).join(&package.namespace);
+        let path = package_dir.join(format!("{}.wasm", stem));
+        let image_path = package_dir.join(format!("{}.ref", stem));
+        // Versions are immutable, so a complete earlier pull is reused as is
+        if let (true, Ok(image)) = (path.is_file(), std::fs::read_to_string(&image_path)) {
+            return Ok(PulledPackage {
+                path,
+                image: image.trim().to_string(),
+            });
+        }
+
+        let metadata = match metadata {
+            Some(metadata) => metadata,
+            None => registry_metadata(&registry, dir)?,
+        };
+        let (oci_registry, prefix) = metadata.oci_location(&registry)?;
+        let repository = format!(
+            "{}{}/{}",
+            prefix.unwrap_or_default(),
+            package.namespace,
+            package.name
+        );
+        let auth = self
+            .registry
+            .get(&registry)
+            .or_else(|| self.registry.get(&oci_registry))
+            .and_then(|config| config.oci.as_ref())
+            .and_then(|oci| oci.auth.as_ref());
+
+        let bytes = OciPull::new(&oci_registry, &repository, auth).pull(&package.version)?;
+        std::fs::create_dir_all(path.parent().unwrap_or(dir))
+            .and_then(|_| std::fs::write(&path, &bytes))
+            .with_context(|| format!("failed to store {} in {}", package, path.display()))?;
+        let image = format!("{}/{}:{}", oci_registry, repository, package.version);
+        std::fs::write(&image_path, &image)
+            .with_context(|| format!("failed to store {} in {}", package, package_dir.display()))?;
+        Ok(PulledPackage { path, image })
+    }
+}
+
+impl WasmPkgConfig {
+    /// Whether `image` is a capability provider or a component, judged by the media types
+    /// of its OCI manifest. `None` when they are neither a provider's nor a component's.
+    pub fn image_kind(&self, image: &ImageRef) -> anyhow::Result<Option<EntityKind>> {
+        let auth = self
+            .registry
+            .get(&image.registry)
+            .and_then(|config| config.oci.as_ref())
+            .and_then(|oci| oci.auth.as_ref());
+        let manifest =
+            OciPull::new(&image.registry, &image.repository, auth).manifest(&image.reference)?;
+        Ok(artifact_kind(&manifest))
+    }
+}
+
+/// The kind of artifact an OCI manifest describes, from its config and layer media types
+fn artifact_kind(manifest: &Value) -> Option<EntityKind> {
+    let layers = manifest["layers"].as_array().into_iter().flatten();
+    let media_types: Vec<_> = std::iter::once(&manifest["config"])
+        .chain(layers)
+        .filter_map(|descriptor| descriptor["mediaType"].as_str())
+        .collect();
+    if media_types
+        .iter()
+        .any(|media_type| media_type.starts_with(PROVIDER_MEDIA_TYPE))
+    {
+        Some(EntityKind::Capability)
+    } else if media_types
+        .iter()
+        .any(|media_type| COMPONENT_MEDIA_TYPES.contains(media_type))
+    {
+        Some(EntityKind::Component)
+    } else {
+        None
+    }
+}
+
+impl RegistryMetadata {
+    /// The OCI registry and namespace prefix packages are stored under
+    fn oci_location(&self, registry: &str) -> anyhow::Result<(String, Option<String>)> {
+        let has_oci = self.oci.is_some() || self.oci_registry.is_some();
+        let protocol = match &self.preferred_protocol {
+            Some(protocol) => protocol.as_str(),
+            None if self.warg.is_some() && !has_oci => "warg",
+            None => "oci",
+        };
+        // A registry preferring another protocol may still serve OCI as well
+        if protocol != "oci" && !has_oci {
+            bail!(
+                "registry {} serves packages over {}, only OCI registries are supported",
+                registry,
+                protocol
+            );
+        }
+        let oci = self.oci.clone().unwrap_or_default();
+        let oci_registry = oci
+            .registry
+            .or_else(|| self.oci_registry.clone())
+            .unwrap_or_else(|| registry.to_string());
+        let prefix = oci
+            .namespace_prefix
+            .or_else(|| self.oci_namespace_prefix.clone());
+        Ok((oci_registry, prefix))
+    }
+}
+
+/// wkg's config file, `$XDG_CONFIG_HOME/wasm-pkg/config.toml`
+fn default_config_path() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config.join("wasm-pkg").join("config.toml"))
+}
+
+/// Fetches the registry's well-known metadata, falling back to the cached copy when the
+/// registry cannot be reached. Registries without metadata are treated as plain OCI
+/// registries.
+fn registry_metadata(registry: &str, dir: &Path) -> anyhow::Result<RegistryMetadata> {
+    let cached = dir.join("registries").join(format!("{}.json", registry));
+    let url = format!("{}/.well-known/wasm-pkg/registry.json", base_url(registry));
+    match fetch::get(&url, &[]) {
+        Ok(response) if response.is_success() => {
+            let metadata: RegistryMetadata = serde_json::from_slice(&response.body)
+                .with_context(|| format!("invalid registry metadata at {}", url))?;
+            // The cache only enables offline use, failing to write it is not an error
+            let _ = std::fs::create_dir_all(dir.join("registries")).and_then(|_| {
+                std::fs::write(&cached, serde_json::to_vec(&metadata).unwrap_or_default())
+            });
+            Ok(metadata)
+        }
+        Ok(_) => Ok(RegistryMetadata::default()),
+        Err(e) => {
+            let content = std::fs::read(&cached).map_err(|_| e)?;
+            println!("Using cached metadata of registry {}", registry);
+            Ok(serde_json::from_slice(&content)?)
+        }
+    }
+}
+
+/// Local registries are reached over plain HTTP, like OCI clients do
+fn base_url(host: &str) -> String {
+    let name = host.rsplit_once(':').map_or(host, |(name, _)| name);
+    if host == "docker.io" {
+        // Docker Hub serves the registry API on another host
+        "https://registry-1.docker.io".to_string()
+    } else if matches!(name, "localhost" | "127.0.0.1" | "[::1]") {
+        format!("http://{}", host)
+    } else {
+        format!("https://{}", host)
+    }
+}
+
+/// Pulls one repository of an OCI registry using the distribution API, with anonymous or
+/// basic-auth bearer tokens
+struct OciPull<'a> {
+    base: String,
+    repository: &'a str,
+    auth: Option<&'a OciAuth>,
+    token: Option<String>,
+}
+
+impl<'a> OciPull<'a> {
+    fn new(registry: &str, repository: &'a str, auth: Option<&'a OciAuth>) -> Self {
+        Self {
+            base: base_url(registry),
+            repository,
+            auth,
+            token: None,
+        }
+    }
+
+    /// Returns the manifest of the image tagged or pinned as `reference`
+    fn manifest(&mut self, reference: &str) -> anyhow::Result<Value> {
+        let manifest_url = format!(
+            "{}/v2/{}/manifests/{}",
+            self.base, self.repository, reference
+        );
+        let response = self.get(&manifest_url, Some(OCI_MANIFEST))?;
+        serde_json::from_slice(&response.body)
+            .with_context(|| format!("invalid OCI manifest at {}", manifest_url))
+    }
+
+    /// Returns the WASM layer of the image tagged `tag`
+    fn pull(&mut self, tag: &str) -> anyhow::Result<Vec<u8>> {
+        let manifest = self.manifest(tag)?;
+        let manifest_url = format!("{}/v2/{}/manifests/{}", self.base, self.repository, tag);
+        let layers = manifest["layers"].as_array().cloned().unwrap_or_default();
+        let layer = layers
+            .iter()
+            .find(|layer| layer["mediaType"] == WASM_LAYER)
+            .or_else(|| layers.first())
+            .with_context(|| format!("{} has no layers", manifest_url))?;
+        let digest = layer["digest"]
+            .as_str()
+            .with_context(|| format!("layer without digest in {}", manifest_url))?;
+
+        let blob_url = format!("{}/v2/{}/blobs/{}", self.base, self.repository, digest);
+        let blob = self.get(&blob_url, None)?.body;
+        if let Some(expected) = digest.strip_prefix("sha256:") {
+            let actual = format!("{:x}", Sha256::digest(&blob));
+            if actual != expected {
+                bail!("{} does not match its digest {}", blob_url, digest);
+            }
+        }
+        Ok(blob)
+    }
+
+    /// GETs `url`, authenticating once if the registry asks for a token
+    fn get(&mut self, url: &str, accept: Option<&str>) -> anyhow::Result<Response> {
+        let mut response = self.send(url, accept)?;
+        if response.status == 401 && self.token.is_none() {
+            let challenge = response
+                .header("www-authenticate")
+                .map(str::to_string)
+                .with_context(|| format!("{} requires authentication", url))?;
+            self.token = Some(self.fetch_token(&challenge)?);
+            response = self.send(url, accept)?;
+        }
+        if !response.is_success() {
+            let detail = String::from_utf8_lossy(&response.body);
+            bail!("{} answered {}: {}", url, response.status, detail.trim());
+        }
+        Ok(response)
+    }
+
+    fn send(&self, url: &str, accept: Option<&str>) -> anyhow::Result<Response> {
+        let authorization = match (&self.token, self.auth) {
+            (Some(token), _) => Some(format!("Bearer {}", token)),
+            (None, Some(auth)) => Some(basic_auth(auth)),
+            (None, None) => None,
+        };
+        let mut headers = Vec::new();
+        if let Some(accept) = accept {
+            headers.push(("accept", accept));
+        }
+        if let Some(authorization) = &authorization {
+            headers.push(("authorization", authorization.as_str()));
+        }
+        fetch::get(url, &headers)
+    }
+
+    /// Follows a `Bearer realm="…",service="…",scope="…"` challenge to a token
+    fn fetch_token(&self, challenge: &str) -> anyhow::Result<String> {
+        let Some(params) = challenge.strip_prefix("Bearer ") else {
+            bail!("unsupported registry authentication {}", challenge);
+        };
+        let mut realm = None;
+        let mut query = Vec::new();
+        for param in split_params(params) {
+            let Some((key, value)) = param.split_once('=') else {
+                continue;
+            };
+            let value = value.trim_matches('"');
+            match key.trim() {
+                "realm" => realm = Some(value.to_string()),
+                key => query.push(format!("{}={}", key, value)),
+            }
+        }
+        if !query.iter().any(|param| param.starts_with("scope=")) {
+            query.push(format!("scope=repository:{}:pull", self.repository));
+        }
+        let realm =
+            realm.with_context(|| format!("token challenge without realm: {}", challenge))?;
+        let url = format!("{}?{}", realm, query.join("&"));
+
+        let mut headers = Vec::new();
+        let authorization = self.auth.map(basic_auth);
+        if let Some(authorization) = &authorization {
+            headers.push(("authorization", authorization.as_str()));
+        }
+        let response = fetch::get(&url, &headers)?;
+        if !response.is_success() {
+            bail!("token request to {} answered {}", realm, response.status);
+        }
+        let body: Value = serde_json::from_slice(&response.body)
+            .with_context(|| format!("invalid token response from {}", realm))?;
+        body["token"]
+            .as_str()
+            .or_else(|| body["access_token"].as_str())
+            .map(str::to_string)
+            .with_context(|| format!("no token in the response from {}", realm))
+    }
+}
+
+/// Splits challenge parameters on commas outside quotes
+fn split_params(params: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut quoted) = (0, false);
+    for (index, c) in params.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                parts.push(&params[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&params[start..]);
+    parts
+}
+
+fn basic_auth(auth: &OciAuth) -> String {
+    format!(
+        "Basic {}",
+        base64(format!("{}:{}", auth.username, auth.password).as_bytes())
+    )
+}
+
+/// Standard base64 with padding
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let value = chunk.iter().enumerate().fold(0u32, |value, (i, byte)| {
+            value | (*byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(value >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(toml: &str) -> WasmPkgConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn package(reference: &str) -> PackageRef {
+        PackageRef::parse(reference).unwrap()
+    }
+
+    fn metadata(json: &str) -> RegistryMetadata {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn base64_pads_partial_chunks() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64(b"user:p\xffss"), "dXNlcjpw/3Nz");
+    }
+
+    #[test]
+    fn split_params_keeps_quoted_commas() {
+        let params = r#"realm="https://auth.example.com/token",service="registry",scope="repository:a/b:pull,push""#;
+        assert_eq!(
+            split_params(params),
+            vec![
+                r#"realm="https://auth.example.com/token""#,
+                r#"service="registry""#,
+                r#"scope="repository:a/b:pull,push""#,
+            ]
+        );
+        assert_eq!(split_params(r#"realm="x""#), vec![r#"realm="x""#]);
+    }
+
+    #[test]
+    fn image_ref_parses_registry_tag_and_digest() {
+        let parse = |image| {
+            ImageRef::parse(image)
+                .map(|image| (image.registry, image.repository, image.reference))
+                .unwrap()
+        };
+        let owned = |registry: &str, repository: &str, reference: &str| {
+            (
+                registry.to_string(),
+                repository.to_string(),
+                reference.to_string(),
+            )
+        };
+        assert_eq!(
+            parse("oci://ghcr.io/wasmcloud/http-server:0.23.2"),
+            owned("ghcr.io", "wasmcloud/http-server", "0.23.2")
+        );
+        assert_eq!(
+            parse("localhost:5000/x"),
+            owned("localhost:5000", "x", "latest")
+        );
+        assert_eq!(
+            parse("localhost:5000/x:1.0"),
+            owned("localhost:5000", "x", "1.0")
+        );
+        assert_eq!(parse("localhost/x"), owned("localhost", "x", "latest"));
+        assert_eq!(
+            parse("ghcr.io/a/b:1.0@sha256:abc"),
+            owned("ghcr.io", "a/b", "sha256:abc")
+        );
+        // A first segment that is not a host is part of a Docker Hub repository
+        assert_eq!(
+            parse("library/nginx:1"),
+            owned("docker.io", "library/nginx", "1")
+        );
+    }
+
+    #[test]
+    fn image_ref_rejects_empty_parts() {
+        for image in ["ghcr.io/", "app:", "app@", "oci://"] {
+            assert_eq!(ImageRef::parse(image), None, "{}", image);
+        }
+    }
+
+    #[test]
+    fn image_ref_digest_and_display() {
+        let pinned = ImageRef::parse("localhost:5000/x@sha256:abc").unwrap();
+        assert_eq!(pinned.digest(), Some("sha256:abc"));
+        assert_eq!(pinned.to_string(), "localhost:5000/x@sha256:abc");
+        let tagged = ImageRef::parse("localhost:5000/x:1.0").unwrap();
+        assert_eq!(tagged.digest(), None);
+        assert_eq!(tagged.to_string(), "localhost:5000/x:1.0");
+    }
+
+    #[test]
+    fn artifact_kind_from_media_types() {
+        let manifest = |config: &str, layer: &str| {
+            serde_json::json!({
+                "config": { "mediaType": config },
+                "layers": [{ "mediaType": layer }],
+            })
+        };
+        assert_eq!(
+            artifact_kind(&manifest(
+                "application/vnd.oci.image.config.v1+json",
+                "application/vnd.wasmcloud.provider.archive.layer.v1+par"
+            )),
+            Some(EntityKind::Capability)
+        );
+        assert_eq!(
+            artifact_kind(&manifest("application/vnd.wasm.config.v0+json", WASM_LAYER)),
+            Some(EntityKind::Component)
+        );
+        assert_eq!(
+            artifact_kind(&manifest(
+                "application/vnd.oci.image.config.v1+json",
+                "application/vnd.oci.image.layer.v1.tar+gzip"
+            )),
+            None
+        );
+        assert_eq!(artifact_kind(&serde_json::json!({})), None);
+    }
+
+    #[test]
+    fn registry_for_prefers_overrides_then_namespaces() {
+        let config = config(
+            r#"
+            default_registry = "default.example.com"
+
+            [namespace_registries]
+            acme = "acme.example.com"
+            custom = { registry = "custom.example.com", metadata = { preferredProtocol = "oci" } }
+
+            [package_registry_overrides]
+            "acme:special" = "special.example.com"
+            "#,
+        );
+        let registry = |reference| config.registry_for(&package(reference)).unwrap();
+        assert_eq!(registry("acme:special@1.0.0").0, "special.example.com");
+        assert_eq!(registry("acme:other@1.0.0").0, "acme.example.com");
+        assert_eq!(registry("other:app@1.0.0").0, "default.example.com");
+        let (custom, metadata) = registry("custom:app@1.0.0");
+        assert_eq!(custom, "custom.example.com");
+        assert!(metadata.is_some());
+    }
+
+    #[test]
+    fn registry_for_falls_back_to_wasi_dev() {
+        let config = config("");
+        let (registry, _) = config.registry_for(&package("wasi:http@0.2.0")).unwrap();
+        assert_eq!(registry, WASI_REGISTRY);
+        let error = config.registry_for(&package("acme:app@1.0.0")).unwrap_err();
+        assert!(error.to_string().contains("no registry for namespace acme"));
+    }
+
+    #[test]
+    fn oci_location_from_registry_metadata() {
+        let location = |json| metadata(json).oci_location("wasi.dev").unwrap();
+        assert_eq!(location("{}"), ("wasi.dev".to_string(), None));
+        assert_eq!(
+            location(r#"{"oci": {"registry": "ghcr.io", "namespacePrefix": "webassembly/"}}"#),
+            ("ghcr.io".to_string(), Some("webassembly/".to_string()))
+        );
+        assert_eq!(
+            location(r#"{"ociRegistry": "localhost:5000", "ociNamespacePrefix": "wasm/"}"#),
+            ("localhost:5000".to_string(), Some("wasm/".to_string()))
+        );
+        // A registry preferring warg may still serve OCI
+        assert_eq!(
+            location(r#"{"preferredProtocol": "warg", "oci": {"registry": "ghcr.io"}}"#).0,
+            "ghcr.io"
+        );
+    }
+
+    #[test]
+    fn oci_location_rejects_warg_only_registries() {
+        for json in [
+            r#"{"warg": {"url": "https://warg.example.com"}}"#,
+            r#"{"preferredProtocol": "warg"}"#,
+        ] {
+            assert!(
+                metadata(json).oci_location("warg.example.com").is_err(),
+                "{}",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn pulled_packages_are_stored_per_registry() {
+        let dir = std::env::temp_dir().join(format!("wail-pkg-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for registry in ["one.example.com", "two.example.com"] {
+            let package_dir = dir.join(registry).join("acme");
+            std::fs::create_dir_all(&package_dir).unwrap();
+            std::fs::write(package_dir.join("app@1.0.0.wasm"), registry).unwrap();
+            std::fs::write(package_dir.join("app@1.0.0.ref"), registry).unwrap();
+        }
+
+        for registry in ["one.example.com", "two.example.com"] {
+            let config = config(&format!("[namespace_registries]\nacme = \"{}\"", registry));
+            let pulled = config.pull(&package("acme:app@1.0.0"), &dir).unwrap();
+            assert_eq!(pulled.image, registry);
+            assert_eq!(std::fs::read_to_string(pulled.path).unwrap(), registry);
+        }
+    }
+}
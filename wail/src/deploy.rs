@@ -0,0 +1,557 @@
+#[cfg(feature = "native")]
+use std::time::Duration;
+
+use wadm_types::Manifest;
+
+use crate::cli::{LatticeArgs, Wail};
+use crate::core::SharedApplications;
+#[cfg(feature = "native")]
+use crate::core::WadmClient;
+use crate::WailPlugin;
+
+/// Unchanged lines shown around each change in a diff
+#[cfg(feature = "native")]
+const DIFF_CONTEXT: usize = 2;
+
+#[cfg(feature = "native")]
+impl WailPlugin {
+    /// Puts the generated manifest into wadm and deploys it, unless the deployed version is
+    /// already identical
+    pub(crate) fn deploy(args: &Wail, lattice: &LatticeArgs, wadm: &Manifest) -> Result<(), ()> {
+        if args.output.is_some() {
+            Self::write_output(args, wadm)?;
+        }
+        let mut client = connect(lattice)?;
+        let name = &wadm.metadata.name;
+
+        let deployed = client.deployed(name).map_err(|e| {
+            eprintln!("Failed to read the deployed version of {}: {:#}", name, e);
+        })?;
+        match &deployed {
+            Some(deployed) => {
+                let changes = diff(deployed, wadm)?;
+                if changes.is_empty() {
+                    println!(
+                        "{} {} is already deployed to lattice {}",
+                        name,
+                        wadm.version(),
+                        client.lattice()
+                    );
+                    return Ok(());
+                }
+                println!(
+                    "Changes from deployed version {}:\n{}",
+                    deployed.version(),
+                    changes
+                );
+                // wadm keeps the first manifest stored under a version
+                if deployed.version() == wadm.version() {
+                    eprintln!(
+                        "{} {} is already deployed with different content, bump --version to \
+                         deploy the changes",
+                        name,
+                        wadm.version()
+                    );
+                    return Err(());
+                }
+            }
+            None => println!(
+                "{} is not deployed to lattice {} yet",
+                name,
+                client.lattice()
+            ),
+        }
+
+        let version = client.put(wadm).map_err(|e| {
+            eprintln!("Failed to put {}: {:#}", name, e);
+        })?;
+        client.deploy(name, &version).map_err(|e| {
+            eprintln!("Failed to deploy {} {}: {:#}", name, version, e);
+        })?;
+        println!(
+            "Deployed {} {} to lattice {}",
+            name,
+            version,
+            client.lattice()
+        );
+        Ok(())
+    }
+
+    /// Diffs the generated manifest against the deployed version. Fails when they differ
+    /// or nothing is deployed, so it can gate CI.
+    pub(crate) fn check_live(lattice: &LatticeArgs, wadm: &Manifest) -> Result<(), ()> {
+        let mut client = connect(lattice)?;
+        let name = &wadm.metadata.name;
+        let deployed = client.deployed(name).map_err(|e| {
+            eprintln!("Failed to read the deployed version of {}: {:#}", name, e);
+        })?;
+        let Some(deployed) = deployed else {
+            eprintln!("{} is not deployed to lattice {}", name, client.lattice());
+            return Err(());
+        };
+
+        let changes = diff(&deployed, wadm)?;
+        if changes.is_empty() {
+            println!(
+                "{} matches deployed version {} in lattice {}",
+                name,
+                deployed.version(),
+                client.lattice()
+            );
+            return Ok(());
+        }
+        eprintln!(
+            "{} differs from deployed version {} in lattice {}:\n{}",
+            name,
+            deployed.version(),
+            client.lattice(),
+            changes
+        );
+        Err(())
+    }
+
+    /// Adds the shared applications deployed in the lattice, so `application:` references
+    /// resolve without local copies of their manifests
+    pub(crate) fn add_deployed_shared(
+        lattice: &LatticeArgs,
+        shared: &mut SharedApplications,
+    ) -> Result<(), ()> {
+        let mut client = connect(lattice)?;
+        let manifests = client.shared_manifests().map_err(|e| {
+            eprintln!(
+                "Failed to list shared applications in lattice {}: {:#}",
+                lattice.lattice, e
+            );
+        })?;
+        for manifest in manifests {
+            println!(
+                "Found shared application {} in lattice {}",
+                manifest.metadata.name, lattice.lattice
+            );
+            shared.add_deployed(&lattice.lattice, manifest);
+        }
+        Ok(())
+    }
+}
+
+/// The plugin is not given sockets, so it cannot reach NATS: the lattice
+/// commands need the native build
+#[cfg(not(feature = "native"))]
+impl WailPlugin {
+    pub(crate) fn deploy(_: &Wail, _: &LatticeArgs, _: &Manifest) -> Result<(), ()> {
+        native_only()
+    }
+
+    pub(crate) fn check_live(_: &LatticeArgs, _: &Manifest) -> Result<(), ()> {
+        native_only()
+    }
+
+    pub(crate) fn add_deployed_shared(
+        _: &LatticeArgs,
+        _: &mut SharedApplications,
+    ) -> Result<(), ()> {
+        native_only()
+    }
+}
+
+#[cfg(not(feature = "native"))]
+fn native_only() -> Result<(), ()> {
+    eprintln!(
+        "Error: deploy and check-live are not available in the wash plugin, which cannot open \
+         connections to NATS; use the native build (cargo build --features native)"
+    );
+    Err(())
+}
+
+#[cfg(feature = "native")]
+fn connect(lattice: &LatticeArgs) -> Result<WadmClient, ()> {
+    WadmClient::connect(
+        &lattice.nats_url,
+        &lattice.lattice,
+        Duration::from_millis(lattice.timeout),
+    )
+    .map_err(|e| {
+        eprintln!("Failed to connect to wadm: {:#}", e);
+    })
+}
+
+/// Line diff of two manifests rendered as YAML, empty when they are equal
+#[cfg(feature = "native")]
+fn diff(old: &Manifest, new: &Manifest) -> Result<String, ()> {
+    let render = |manifest: &Manifest| {
+        serde_yaml::to_string(manifest).map_err(|e| {
+            eprintln!("Failed to serialize manifest: {}", e);
+        })
+    };
+    let (old, new) = (render(old)?, render(new)?);
+    Ok(line_diff(
+        &old.lines().collect::<Vec<_>>(),
+        &new.lines().collect::<Vec<_>>(),
+    ))
+}
+
+/// Unified-style diff from the longest common subsequence of lines
+#[cfg(feature = "native")]
+fn line_diff(old: &[&str], new: &[&str]) -> String {
+    // common[i][j] is the LCS length of old[i..] and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(('-', old[i]));
+            i += 1;
+        } else {
+            lines.push(('+', new[j]));
+            j += 1;
+        }
+    }
+
+    let changed: Vec<usize> = (0..lines.len()).filter(|&n| lines[n].0 != ' ').collect();
+    let mut out = Vec::new();
+    let mut shown_until = 0;
+    for &n in &changed {
+        let start = n.saturating_sub(DIFF_CONTEXT).max(shown_until);
+        if start > shown_until {
+            out.push("  ...".to_string());
+        }
+        let end = (n + DIFF_CONTEXT + 1).min(lines.len());
+        for (marker, line) in &lines[start..end] {
+            out.push(format!("{} {}", marker, line));
+        }
+        shown_until = shown_until.max(end);
+    }
+    out.join("\n")
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::process::{Child, Command, Stdio};
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+
+    use clap::Parser;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    const LATTICE: &str = "default";
+
+    /// A `nats-server` from the PATH on a free local port, stopped when dropped
+    struct NatsServer {
+        child: Child,
+        url: String,
+    }
+
+    impl NatsServer {
+        /// Starts the server. The tests using it are ignored by default since they need
+        /// `nats-server` installed.
+        fn start() -> Self {
+            let port = free_port();
+            let child = Command::new("nats-server")
+                .args(["-a", "127.0.0.1", "-p", &port.to_string()])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .expect("nats-server is not on the PATH");
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while TcpStream::connect(("127.0.0.1", port)).is_err() {
+                assert!(Instant::now() < deadline, "nats-server did not start");
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            Self {
+                child,
+                url: format!("nats://127.0.0.1:{}", port),
+            }
+        }
+    }
+
+    impl Drop for NatsServer {
+        fn drop(&mut self) {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+
+    fn free_port() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    /// In-memory stand-in for the wadm model API
+    #[derive(Default)]
+    struct Wadm {
+        /// Stored manifests by application, in the order they were put
+        models: HashMap<String, Vec<Value>>,
+        deployed: HashMap<String, String>,
+        /// Operations received, such as `model.put` or `model.deploy.my-app`
+        calls: Vec<String>,
+    }
+
+    impl Wadm {
+        fn handle(&mut self, operation: &str, body: &[u8]) -> Value {
+            self.calls.push(operation.to_string());
+            let request: Value = serde_json::from_slice(body).unwrap_or_default();
+            match operation.strip_prefix("model.").unwrap_or(operation) {
+                "list" => {
+                    let models: Vec<_> = self
+                        .models
+                        .keys()
+                        .map(|name| json!({ "name": name, "deployed_version": self.deployed.get(name) }))
+                        .collect();
+                    json!({ "result": "success", "models": models })
+                }
+                "put" => {
+                    let name = request["metadata"]["name"].as_str().unwrap().to_string();
+                    let version = request["metadata"]["annotations"]["version"].clone();
+                    let versions = self.models.entry(name).or_default();
+                    if versions
+                        .iter()
+                        .any(|m| m["metadata"]["annotations"]["version"] == version)
+                    {
+                        return json!({ "result": "error", "message": "version already exists" });
+                    }
+                    versions.push(request);
+                    json!({ "result": "created", "current_version": version })
+                }
+                operation => {
+                    let (action, name) = operation.split_once('.').unwrap();
+                    let manifest = self.models.get(name).and_then(|versions| {
+                        versions
+                            .iter()
+                            .find(|m| m["metadata"]["annotations"]["version"] == request["version"])
+                    });
+                    match (action, manifest) {
+                        ("get", Some(manifest)) => {
+                            json!({ "result": "success", "manifest": manifest })
+                        }
+                        ("deploy", Some(_)) => {
+                            let version = request["version"].as_str().unwrap().to_string();
+                            self.deployed.insert(name.to_string(), version);
+                            json!({ "result": "acknowledged" })
+                        }
+                        _ => json!({ "result": "not_found", "message": "no such version" }),
+                    }
+                }
+            }
+        }
+
+        fn count(&self, operation: &str) -> usize {
+            self.calls.iter().filter(|call| *call == operation).count()
+        }
+    }
+
+    /// Subscribes to `wadm.api.<lattice>.model.>` on the server and answers from a `Wadm`
+    fn respond(url: &str) -> Arc<Mutex<Wadm>> {
+        let stream = TcpStream::connect(url.trim_start_matches("nats://")).unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        write!(
+            writer,
+            "CONNECT {{\"verbose\":false}}\r\nSUB wadm.api.{}.model.> 1\r\nPING\r\n",
+            LATTICE
+        )
+        .unwrap();
+        // The PONG confirms the subscription before any request is sent
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line.trim_end(), "PONG");
+
+        let wadm = Arc::new(Mutex::new(Wadm::default()));
+        let state = wadm.clone();
+        std::thread::spawn(move || loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                return;
+            }
+            if line.starts_with("PING") {
+                let _ = writer.write_all(b"PONG\r\n");
+                continue;
+            }
+            // MSG <subject> <sid> <reply-to> <#bytes>
+            let Some(header) = line.trim_end().strip_prefix("MSG ") else {
+                continue;
+            };
+            let fields: Vec<_> = header.split_whitespace().collect();
+            let mut body = vec![0; fields[3].parse::<usize>().unwrap() + 2];
+            reader.read_exact(&mut body).unwrap();
+            body.truncate(body.len() - 2);
+
+            let prefix = format!("wadm.api.{}.", LATTICE);
+            let operation = fields[0].strip_prefix(&prefix).unwrap();
+            let response = state.lock().unwrap().handle(operation, &body).to_string();
+            let _ = write!(
+                writer,
+                "PUB {} {}\r\n{}\r\n",
+                fields[2],
+                response.len(),
+                response
+            );
+        });
+        wadm
+    }
+
+    fn lattice(url: &str) -> LatticeArgs {
+        LatticeArgs {
+            nats_url: url.to_string(),
+            lattice: LATTICE.to_string(),
+            timeout: 2000,
+        }
+    }
+
+    fn manifest(version: &str, image: &str) -> Manifest {
+        serde_yaml::from_str(&format!(
+            r#"
+apiVersion: core.oam.dev/v1beta1
+kind: Application
+metadata:
+  name: my-app
+  annotations:
+    version: {version}
+spec:
+  components:
+    - name: http
+      type: component
+      properties:
+        image: {image}
+"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    #[ignore = "needs nats-server"]
+    fn deploy_puts_and_deploys_new_application() {
+        let server = NatsServer::start();
+        let wadm = respond(&server.url);
+        let args = Wail::parse_from(["wail"]);
+
+        WailPlugin::deploy(
+            &args,
+            &lattice(&server.url),
+            &manifest("0.1.0", "http:0.1.0"),
+        )
+        .unwrap();
+
+        let wadm = wadm.lock().unwrap();
+        assert_eq!(wadm.count("model.put"), 1);
+        assert_eq!(wadm.count("model.deploy.my-app"), 1);
+        assert_eq!(wadm.deployed["my-app"], "0.1.0");
+    }
+
+    #[test]
+    #[ignore = "needs nats-server"]
+    fn deploy_leaves_identical_deployment_alone() {
+        let server = NatsServer::start();
+        let wadm = respond(&server.url);
+        let args = Wail::parse_from(["wail"]);
+        let lattice = lattice(&server.url);
+
+        WailPlugin::deploy(&args, &lattice, &manifest("0.1.0", "http:0.1.0")).unwrap();
+        WailPlugin::deploy(&args, &lattice, &manifest("0.1.0", "http:0.1.0")).unwrap();
+
+        assert_eq!(wadm.lock().unwrap().count("model.put"), 1);
+    }
+
+    #[test]
+    #[ignore = "needs nats-server"]
+    fn deploy_requires_version_bump_for_changes() {
+        let server = NatsServer::start();
+        let wadm = respond(&server.url);
+        let args = Wail::parse_from(["wail"]);
+        let lattice = lattice(&server.url);
+
+        WailPlugin::deploy(&args, &lattice, &manifest("0.1.0", "http:0.1.0")).unwrap();
+        assert!(WailPlugin::deploy(&args, &lattice, &manifest("0.1.0", "http:0.2.0")).is_err());
+        assert_eq!(wadm.lock().unwrap().count("model.put"), 1);
+
+        WailPlugin::deploy(&args, &lattice, &manifest("0.2.0", "http:0.2.0")).unwrap();
+        assert_eq!(wadm.lock().unwrap().deployed["my-app"], "0.2.0");
+    }
+
+    #[test]
+    #[ignore = "needs nats-server"]
+    fn check_live_passes_without_changes() {
+        let server = NatsServer::start();
+        let wadm = respond(&server.url);
+        let args = Wail::parse_from(["wail"]);
+        let lattice = lattice(&server.url);
+
+        WailPlugin::deploy(&args, &lattice, &manifest("0.1.0", "http:0.1.0")).unwrap();
+        WailPlugin::check_live(&lattice, &manifest("0.1.0", "http:0.1.0")).unwrap();
+
+        assert_eq!(wadm.lock().unwrap().count("model.get.my-app"), 1);
+    }
+
+    #[test]
+    #[ignore = "needs nats-server"]
+    fn check_live_fails_on_changes() {
+        let server = NatsServer::start();
+        let _wadm = respond(&server.url);
+        let args = Wail::parse_from(["wail"]);
+        let lattice = lattice(&server.url);
+
+        // Nothing is deployed yet
+        assert!(WailPlugin::check_live(&lattice, &manifest("0.1.0", "http:0.1.0")).is_err());
+
+        WailPlugin::deploy(&args, &lattice, &manifest("0.1.0", "http:0.1.0")).unwrap();
+        assert!(WailPlugin::check_live(&lattice, &manifest("0.2.0", "http:0.2.0")).is_err());
+    }
+
+    #[test]
+    #[ignore = "needs nats-server"]
+    fn check_live_fails_without_wadm() {
+        let server = NatsServer::start();
+        let mut lattice = lattice(&server.url);
+        lattice.timeout = 200;
+
+        let error = WadmClient::connect(&lattice.nats_url, LATTICE, Duration::from_millis(200))
+            .unwrap()
+            .list()
+            .unwrap_err();
+        assert!(error.to_string().contains("is anything subscribed to it?"));
+        assert!(WailPlugin::check_live(&lattice, &manifest("0.1.0", "http:0.1.0")).is_err());
+    }
+
+    #[test]
+    fn unreachable_server_fails() {
+        let url = format!("nats://127.0.0.1:{}", free_port());
+        let error = WadmClient::connect(&url, LATTICE, Duration::from_millis(500))
+            .err()
+            .unwrap();
+        assert!(error
+            .to_string()
+            .starts_with("failed to connect to NATS at 127.0.0.1:"));
+        assert!(WailPlugin::check_live(&lattice(&url), &manifest("0.1.0", "http:0.1.0")).is_err());
+    }
+
+    #[test]
+    fn line_diff_shows_changes_with_context() {
+        let old = ["a", "b", "c", "d", "e", "f", "g", "h", "i"];
+        let new = ["a", "B", "c", "d", "e", "f", "g", "h", "i", "j"];
+        assert_eq!(
+            line_diff(&old, &new),
+            "  a\n- b\n+ B\n  c\n  d\n  ...\n  h\n  i\n+ j"
+        );
+        assert_eq!(line_diff(&old, &old), "");
+    }
+}
@@ -0,0 +1,456 @@
+use crate::cli::Wail;
+use crate::core::{
+    ComponentCache, ConstructorManifest, ImportPolicy, LinkConstructor, ValidationReport,
+    RUNTIME_INTERFACES,
+};
+use crate::models::{InterfaceIdentifier, InterfaceInfo};
+use crate::WailPlugin;
+
+impl WailPlugin {
+    /// Resolves the links of the inputs and prints how the target of each import of
+    /// `component`, or only of `interface`, was decided
+    pub(crate) fn explain(
+        args: &Wail,
+        cache: &mut ComponentCache,
+        component: &str,
+        interface: Option<&str>,
+    ) -> Result<(), ()> {
+        let interface = match interface {
+            Some(interface) => Some(InterfaceIdentifier::parse(interface).ok_or_else(|| {
+                eprintln!(
+                    "Invalid interface {}: expected namespace:package/interface",
+                    interface
+                );
+            })?),
+            None => None,
+        };
+
+        let mut constructor = Self::load_inputs(args, cache)?;
+        let Some(info) = constructor.component_interfaces.get(component).cloned() else {
+            eprintln!(
+                "{} is not a component of the inputs with known interfaces",
+                component
+            );
+            return Err(());
+        };
+        let imports: Vec<_> = info
+            .imports
+            .iter()
+            .filter(|import| {
+                interface.as_ref().is_none_or(|id| {
+                    id.namespace == import.namespace
+                        && id.package == import.package
+                        && id.name == import.name
+                })
+            })
+            .collect();
+        if let (Some(id), true) = (&interface, imports.is_empty()) {
+            eprintln!("{} does not import {}", component, id);
+            return Err(());
+        }
+
+        // Targets set before resolution tell explicit links from resolved ones
+        let configured = constructor.link_constructors.clone();
+        let report = constructor.validate().map_err(|e| {
+            eprintln!("Validation failed: {}", e);
+        })?;
+
+        println!("\nLink resolution for {}:", component);
+        if imports.is_empty() {
+            println!("  {} imports nothing", component);
+        }
+        for import in imports {
+            for line in explain_import(&constructor, &configured, &report, component, import) {
+                println!("{}", line);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The lines explaining how the target of `component`'s import `import` was decided
+fn explain_import(
+    constructor: &ConstructorManifest,
+    configured: &[LinkConstructor],
+    report: &ValidationReport,
+    component: &str,
+    import: &InterfaceInfo,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    let id = format!("{}:{}/{}", import.namespace, import.package, import.name);
+    match &import.version {
+        Some(version) => lines.push(format!("\n{}@{}", id, version)),
+        None => lines.push(format!("\n{}", id)),
+    }
+    if RUNTIME_INTERFACES.iter().any(|(ns, pkg, name)| {
+        *ns == import.namespace && *pkg == import.package && *name == import.name
+    }) {
+        lines.push("  satisfied by the wasmCloud runtime, not linked".to_string());
+        return lines;
+    }
+    if !import.resources_from.is_empty() {
+        lines.push(format!(
+            "  uses resources of {}, which has to be linked to the same target",
+            import.resources_from.join(", ")
+        ));
+    }
+
+    // Explicit links in the order the inputs applied them
+    let settings: Vec<_> = constructor
+        .link_settings
+        .iter()
+        .filter(|s| {
+            s.component == component
+                && s.namespace == import.namespace
+                && s.package == import.package
+                && s.interface == import.name
+        })
+        .collect();
+    for (index, setting) in settings.iter().enumerate() {
+        let overrides = setting.target.is_some()
+            && settings[..index].iter().any(|earlier| {
+                earlier.link_name == setting.link_name
+                    && earlier.target.is_some()
+                    && earlier.target != setting.target
+            });
+        lines.push(format!(
+            "  {} link set to {} by {}{}",
+            setting.link_name.as_deref().unwrap_or("default"),
+            setting.target.as_deref().unwrap_or("no target"),
+            setting.input,
+            if overrides {
+                ", overriding the target set before"
+            } else {
+                ""
+            }
+        ));
+    }
+
+    // Every other component saturation considers
+    let probe = LinkConstructor {
+        pre_component_id: component.to_string(),
+        post_component_id: None,
+        interfaces: vec![import.name.clone()],
+        namespace: import.namespace.clone(),
+        package: import.package.clone(),
+        link_name: None,
+    };
+    let mut exporters = 0;
+    let mut others = Vec::new();
+    for candidate in &constructor.spec.components {
+        if candidate.name == component {
+            continue;
+        }
+        match constructor.matching_export(&candidate.name, &probe) {
+            Some((_, None)) => {
+                exporters += 1;
+                lines.push(format!("  candidate {} exports it", candidate.name));
+            }
+            Some((export, Some(rule))) => {
+                exporters += 1;
+                lines.push(format!(
+                    "  candidate {} exports {}:{}/{}{} (alias rule {})",
+                    candidate.name,
+                    export.namespace,
+                    export.package,
+                    export.name,
+                    export
+                        .version
+                        .as_ref()
+                        .map(|v| format!("@{}", v))
+                        .unwrap_or_default(),
+                    rule
+                ));
+            }
+            None if !constructor
+                .component_interfaces
+                .contains_key(&candidate.name) =>
+            {
+                lines.push(format!(
+                    "  candidate {} has unknown interfaces and is only used when linked explicitly",
+                    candidate.name
+                ));
+            }
+            None => others.push(candidate.name.as_str()),
+        }
+    }
+    if !others.is_empty() {
+        lines.push(format!("  not exported by {}", others.join(", ")));
+    }
+
+    for (index, link) in constructor.link_constructors.iter().enumerate() {
+        if !same_import(link, &probe) {
+            continue;
+        }
+        let before = configured
+            .get(index)
+            .and_then(|l| l.post_component_id.as_deref());
+        lines.push(format!(
+            "  {} link -> {}: {}",
+            link.link_name(),
+            link.post_component_id.as_deref().unwrap_or("unlinked"),
+            decision(
+                constructor,
+                configured,
+                report,
+                import,
+                link,
+                before,
+                exporters
+            )
+        ));
+    }
+
+    for (index, link) in &report.link_errors {
+        if link.pre_component_id == component
+            && link.namespace == import.namespace
+            && link.package == import.package
+            && link.interfaces[0] == import.name
+        {
+            lines.push(format!("  error: {}", report.errors[*index]));
+        }
+    }
+    lines
+}
+
+/// The rule that decided `link`'s target
+fn decision(
+    constructor: &ConstructorManifest,
+    configured: &[LinkConstructor],
+    report: &ValidationReport,
+    import: &InterfaceInfo,
+    link: &LinkConstructor,
+    before: Option<&str>,
+    exporters: usize,
+) -> String {
+    let via = report
+        .aliased_links
+        .iter()
+        .find(|aliased| same_link(&aliased.link, link))
+        .map(|aliased| format!(" through alias rule {}", aliased.rule))
+        .unwrap_or_default();
+
+    let Some(target) = link.post_component_id.as_deref() else {
+        return match constructor.import_policy(link) {
+            Some(ImportPolicy::Optional) => {
+                "optional and no component exports it, left unlinked".to_string()
+            }
+            Some(policy) => format!("{} import, skipped", policy),
+            None => "no component exports it".to_string(),
+        };
+    };
+    if before.is_some() {
+        return format!("explicit link{}", via);
+    }
+
+    // A resolved link moved to the explicit target of the interface defining its resources
+    let companion = import.resources_from.iter().find(|origin| {
+        configured.iter().any(|l| {
+            l.pre_component_id == link.pre_component_id
+                && l.post_component_id.as_deref() == Some(target)
+                && l.link_name() == link.link_name()
+                && InterfaceIdentifier::parse(origin).is_some_and(|id| {
+                    id.namespace == l.namespace
+                        && id.package == l.package
+                        && id.name == l.interfaces[0]
+                })
+        })
+    });
+    if let Some(companion) = companion {
+        return format!(
+            "follows the explicit link of {}, which defines its resources{}",
+            companion, via
+        );
+    }
+    if report
+        .discovered_links
+        .iter()
+        .any(|discovered| same_link(discovered, link))
+    {
+        return format!(
+            "follows the target of the import using its resources{}",
+            via
+        );
+    }
+    if exporters > 1 {
+        format!(
+            "first declared of {} exporting components{}; link it explicitly to choose another",
+            exporters, via
+        )
+    } else {
+        format!("the only component exporting it{}", via)
+    }
+}
+
+/// Whether `link` binds the same import as `other`, under any link name
+fn same_import(link: &LinkConstructor, other: &LinkConstructor) -> bool {
+    link.pre_component_id == other.pre_component_id
+        && link.namespace == other.namespace
+        && link.package == other.package
+        && link.interfaces[0] == other.interfaces[0]
+}
+
+fn same_link(link: &LinkConstructor, other: &LinkConstructor) -> bool {
+    same_import(link, other) && link.link_name() == other.link_name()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::models::{ComponentInfo, EntityKind, InterfaceAlias};
+
+    /// `namespace:package/interface[@version]`
+    fn interface(reference: &str) -> InterfaceInfo {
+        let (unversioned, version) = match reference.split_once('@') {
+            Some((unversioned, version)) => (unversioned, Some(version.to_string())),
+            None => (reference, None),
+        };
+        let id = InterfaceIdentifier::parse(unversioned).unwrap();
+        InterfaceInfo {
+            name: id.name,
+            namespace: id.namespace,
+            package: id.package,
+            version,
+            functions: Vec::new(),
+            resources_from: Vec::new(),
+        }
+    }
+
+    fn add(manifest: &mut ConstructorManifest, name: &str, imports: &[&str], exports: &[&str]) {
+        let info = ComponentInfo {
+            imports: imports.iter().map(|i| interface(i)).collect(),
+            exports: exports.iter().map(|e| interface(e)).collect(),
+            package: None,
+            claims: None,
+            producers: Default::default(),
+            registry: None,
+            version: None,
+        };
+        manifest
+            .merge_component_info(
+                name.to_string(),
+                info,
+                PathBuf::from(format!("{}.wasm", name)),
+                EntityKind::Component,
+            )
+            .unwrap();
+    }
+
+    /// Resolves the links like `wail explain` and returns the lines for `app`'s first import
+    fn explain(mut manifest: ConstructorManifest) -> Vec<String> {
+        let configured = manifest.link_constructors.clone();
+        let report = manifest.validate().unwrap();
+        let import = manifest.component_interfaces["app"].imports[0].clone();
+        explain_import(&manifest, &configured, &report, "app", &import)
+    }
+
+    #[test]
+    fn explains_first_declared_exporter() {
+        let mut manifest = ConstructorManifest::new();
+        add(&mut manifest, "app", &["acme:kv/store@0.2.0"], &[]);
+        add(&mut manifest, "redis", &[], &["acme:kv/store@0.2.1"]);
+        add(&mut manifest, "http", &[], &["wasi:http/incoming-handler"]);
+        add(&mut manifest, "memory", &[], &["acme:kv/store@0.2.0"]);
+
+        assert_eq!(
+            explain(manifest),
+            [
+                "\nacme:kv/store@0.2.0",
+                "  candidate redis exports it",
+                "  candidate memory exports it",
+                "  not exported by http",
+                "  default link -> redis: first declared of 2 exporting components; link it \
+                 explicitly to choose another",
+            ]
+        );
+    }
+
+    #[test]
+    fn explains_explicit_link() {
+        let mut manifest = ConstructorManifest::new();
+        add(&mut manifest, "app", &["acme:kv/store"], &[]);
+        add(&mut manifest, "redis", &[], &["acme:kv/store"]);
+        add(&mut manifest, "memory", &[], &["acme:kv/store"]);
+        manifest
+            .apply_link(
+                "components.yaml",
+                "app",
+                "acme",
+                "kv",
+                &["store".to_string()],
+                None,
+                Some("memory".to_string()),
+            )
+            .unwrap();
+
+        let lines = explain(manifest);
+        assert_eq!(lines[1], "  default link set to memory by components.yaml");
+        assert_eq!(
+            lines.last().unwrap(),
+            "  default link -> memory: explicit link"
+        );
+    }
+
+    #[test]
+    fn explains_alias_and_missing_exporters() {
+        let mut manifest = ConstructorManifest::new();
+        add(
+            &mut manifest,
+            "app",
+            &["wasi:keyvalue/store@0.2.0-draft"],
+            &[],
+        );
+        add(&mut manifest, "kv", &[], &["acme:kv/store@1.0.0"]);
+        manifest
+            .add_aliases(&[InterfaceAlias {
+                name: Some("kv bridge".to_string()),
+                interfaces: vec![
+                    "wasi:keyvalue/store@0.2.0-draft".to_string(),
+                    "acme:kv/store".to_string(),
+                ],
+            }])
+            .unwrap();
+        let lines = explain(manifest);
+        assert_eq!(
+            lines[1..],
+            [
+                "  candidate kv exports acme:kv/store@1.0.0 (alias rule kv bridge)",
+                "  default link -> kv: the only component exporting it through alias rule kv bridge",
+            ]
+        );
+
+        let mut manifest = ConstructorManifest::new();
+        add(&mut manifest, "app", &["acme:kv/store"], &[]);
+        add(&mut manifest, "http", &[], &["wasi:http/incoming-handler"]);
+        let lines = explain(manifest);
+        assert_eq!(
+            lines[1..],
+            [
+                "  not exported by http",
+                "  default link -> unlinked: no component exports it",
+                "  error: Interface error: No component found that exports interface \
+                 acme:kv:store required by app",
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_of_similarly_named_components_are_not_shown() {
+        // Only the error of app-gateway mentions the interface, and it contains "app"
+        let mut manifest = ConstructorManifest::new();
+        add(&mut manifest, "app", &["acme:kv/store"], &[]);
+        add(&mut manifest, "app-gateway", &["acme:kv/store"], &[]);
+        manifest
+            .set_import_policy(Some("app"), "acme:kv/store", ImportPolicy::Optional)
+            .unwrap();
+
+        let lines = explain(manifest);
+        assert!(
+            lines.iter().all(|line| !line.starts_with("  error:")),
+            "{:?}",
+            lines
+        );
+    }
+}
@@ -0,0 +1,456 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::Write;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::core::{process_wasm_file, providers_exporting, WashProject, RUNTIME_INTERFACES};
+use crate::models::{parse_components, ComponentInfo, InterfaceInfo};
+use crate::WailPlugin;
+
+/// Directories that never contain components worth linking
+const SKIPPED_DIRS: &[&str] = &["node_modules", "deps", "incremental", "wail-stubs"];
+
+/// An artifact found while scanning
+struct Found {
+    /// Suggested entity name
+    name: String,
+    /// Path of the artifact, which may not exist yet for unbuilt wash projects
+    path: PathBuf,
+    /// Directory of the wash project the artifact is built by
+    project: Option<PathBuf>,
+    capability: bool,
+    info: Option<ComponentInfo>,
+    note: Option<String>,
+}
+
+impl WailPlugin {
+    /// Scans `dir` for components and wash projects and writes a starter components file
+    pub(crate) fn init(dir: &Path, output: &Path, force: bool) -> Result<(), ()> {
+        if output.exists() && !force {
+            eprintln!(
+                "{} already exists, pass --force to overwrite it",
+                output.display()
+            );
+            return Err(());
+        }
+
+        let mut found = Vec::new();
+        scan(dir, &mut found, &mut HashSet::new()).map_err(|e| {
+            eprintln!("Failed to scan {}: {}", dir.display(), e);
+        })?;
+        if found.is_empty() {
+            eprintln!("No components or wash projects found in {}", dir.display());
+            return Err(());
+        }
+
+        let content = render(dir, &found);
+        // Never hand out a file WAIL itself cannot read
+        if let Err(e) = parse_components(output, &content) {
+            eprintln!("Generated an invalid components file:\n{}", e);
+            return Err(());
+        }
+
+        std::fs::write(output, &content).map_err(|e| {
+            eprintln!("Failed to write {}: {}", output.display(), e);
+        })?;
+        println!(
+            "Wrote {} scanned entities to {}",
+            found.len(),
+            output.display()
+        );
+        Ok(())
+    }
+}
+
+/// Scans `dir` and its subdirectories. Directories already in `visited` (by canonical path)
+/// are skipped, so symlink loops and directories linked twice are scanned once.
+fn scan(dir: &Path, found: &mut Vec<Found>, visited: &mut HashSet<PathBuf>) -> std::io::Result<()> {
+    if !visited.insert(dir.canonicalize()?) {
+        return Ok(());
+    }
+    let manifest = WashProject::manifest_path(dir);
+    if manifest.is_file() {
+        // The project's build output is everything we need from it
+        match scan_project(dir) {
+            Ok(project) => {
+                add(found, project);
+                return Ok(());
+            }
+            Err(e) => println!("Skipping {}: {}", manifest.display(), e),
+        }
+    }
+
+    let mut entries: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .collect();
+    entries.sort();
+
+    for path in entries {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if path.is_dir() {
+            if !file_name.starts_with('.') && !SKIPPED_DIRS.contains(&file_name.as_str()) {
+                scan(&path, found, visited)?;
+            }
+        } else if file_name.ends_with(".wasm") {
+            if !is_component(&path) {
+                println!("Skipping {}: core module, not a component", path.display());
+                continue;
+            }
+            match process_wasm_file(&file_name, &path) {
+                Ok(info) => add(
+                    found,
+                    Found {
+                        name: entity_name(&file_name),
+                        path,
+                        project: None,
+                        capability: false,
+                        info: Some(info),
+                        note: None,
+                    },
+                ),
+                Err(e) => println!("Skipping {}: {}", path.display(), e),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn scan_project(dir: &Path) -> anyhow::Result<Found> {
+    let project = WashProject::load(dir)?;
+    let artifact = project.artifact();
+    let note = artifact
+        .is_none()
+        .then(|| format!("Not built yet, run `wash build` in {}", dir.display()));
+
+    let info = match &artifact {
+        Some(path) if !project.is_provider() => Some(process_wasm_file(&project.name, path)?),
+        _ => None,
+    };
+    Ok(Found {
+        name: entity_name(&project.name),
+        path: artifact.unwrap_or_else(|| project.expected_artifact()),
+        project: Some(dir.to_path_buf()),
+        capability: project.is_provider(),
+        info,
+        note,
+    })
+}
+
+/// Whether the binary at `path` is a component rather than a core module, judged by the
+/// layer field of its header
+fn is_component(path: &Path) -> bool {
+    let mut header = [0u8; 8];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|_| header[..4] == *b"\0asm" && header[6..8] == [0x01, 0x00])
+}
+
+/// `http_hello_world_s.wasm` becomes `http-hello-world`
+fn entity_name(file_name: &str) -> String {
+    let stem = file_name.trim_end_matches(".wasm");
+    let stem = stem.strip_suffix("_s").unwrap_or(stem);
+    stem.to_lowercase().replace(['_', '.', ' '], "-")
+}
+
+/// Adds `entry`, suffixing its name when another entity already uses it
+fn add(found: &mut Vec<Found>, mut entry: Found) {
+    let base = entry.name.clone();
+    let mut n = 2;
+    while found.iter().any(|f| f.name == entry.name) {
+        entry.name = format!("{}-{}", base, n);
+        n += 1;
+    }
+    found.push(entry);
+}
+
+fn qualified(interface: &InterfaceInfo) -> String {
+    format!(
+        "{}:{}/{}",
+        interface.namespace, interface.package, interface.name
+    )
+}
+
+fn render(dir: &Path, found: &[Found]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Generated by `wail init` in {}", dir.display());
+    let _ = writeln!(
+        out,
+        "# Review the entities, then run: wail --components <this file>"
+    );
+    let _ = writeln!(out, "\nentities:");
+
+    for entity in found {
+        let _ = writeln!(out);
+        if let Some(note) = &entity.note {
+            let _ = writeln!(out, "  # {}", note);
+        }
+        if let Some(info) = &entity.info {
+            for (label, interfaces) in [("Imports", &info.imports), ("Exports", &info.exports)] {
+                if !interfaces.is_empty() {
+                    let names: Vec<_> = interfaces.iter().map(qualified).collect();
+                    let _ = writeln!(out, "  # {}: {}", label, names.join(", "));
+                }
+            }
+        }
+        let _ = writeln!(out, "  - name: {}", entity.name);
+        if entity.capability {
+            let _ = writeln!(out, "    kind: capability");
+        }
+        let _ = writeln!(out, "    source:");
+        match &entity.project {
+            Some(project) => {
+                let _ = writeln!(out, "      project: {}", project.display());
+            }
+            None => {
+                let _ = writeln!(out, "      path: file://{}", entity.path.display());
+            }
+        }
+    }
+
+    // Imports no scanned component exports, grouped by the provider that would satisfy them
+    let exported: BTreeSet<String> = found
+        .iter()
+        .filter_map(|f| f.info.as_ref())
+        .flat_map(|info| info.exports.iter().map(qualified))
+        .collect();
+    let mut suggested = BTreeMap::new();
+    let mut unknown = BTreeSet::new();
+    for import in found
+        .iter()
+        .filter_map(|f| f.info.as_ref())
+        .flat_map(|info| info.imports.iter())
+    {
+        let name = qualified(import);
+        if exported.contains(&name)
+            || (import.namespace == "wasi"
+                && RUNTIME_INTERFACES
+                    .iter()
+                    .any(|(_, pkg, iface)| pkg == &import.package && iface == &import.name))
+        {
+            continue;
+        }
+
+        let providers = providers_exporting(&import.namespace, &import.package, &import.name);
+        // Prefer a provider that is already suggested for another import
+        let Some(chosen) = providers
+            .iter()
+            .find(|p| suggested.contains_key(p.name))
+            .or(providers.first())
+        else {
+            unknown.insert(name);
+            continue;
+        };
+        let (_, interfaces, alternatives) = suggested
+            .entry(chosen.name)
+            .or_insert_with(|| (*chosen, Vec::new(), BTreeSet::new()));
+        if !interfaces.contains(&name) {
+            interfaces.push(name);
+        }
+        alternatives.extend(
+            providers
+                .iter()
+                .filter(|p| p.name != chosen.name)
+                .map(|p| p.name),
+        );
+    }
+
+    for (name, (provider, interfaces, alternatives)) in &suggested {
+        let entity = if found.iter().any(|f| f.name == *name) {
+            format!("{}-provider", name)
+        } else {
+            name.to_string()
+        };
+        let _ = writeln!(out);
+        let _ = writeln!(out, "  # Suggested provider for {}", interfaces.join(", "));
+        if !alternatives.is_empty() {
+            let alternatives: Vec<_> = alternatives.iter().copied().collect();
+            let _ = writeln!(out, "  # Alternatives: {}", alternatives.join(", "));
+        }
+        let _ = writeln!(out, "  - name: {}", entity);
+        let _ = writeln!(out, "    kind: capability");
+        let _ = writeln!(out, "    source:");
+        let _ = writeln!(
+            out,
+            "      # Pin a release tag, e.g. {}:<version>",
+            provider.image
+        );
+        let _ = writeln!(
+            out,
+            "      reference: oci://ghcr.io/wasmcloud/{}",
+            provider.image
+        );
+    }
+
+    if !unknown.is_empty() {
+        let _ = writeln!(out);
+        let _ = writeln!(
+            out,
+            "# No known provider exports these imports. Add an entity that does, or run with"
+        );
+        let _ = writeln!(out, "# --stub-unresolved to generate stubs:");
+        for name in &unknown {
+            let _ = writeln!(out, "#   - {}", name);
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_encoder::{
+        CodeSection, ExportKind, ExportSection, Function, FunctionSection, Instruction, Module,
+        TypeSection,
+    };
+    use wit_component::{ComponentEncoder, StringEncoding};
+    use wit_parser::Resolve;
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wail-init-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A component exporting `acme:api/handler` with a single no-op function
+    fn component() -> Vec<u8> {
+        let mut resolve = Resolve::default();
+        let package = resolve
+            .push_str(
+                "component.wit",
+                "package acme:api;\ninterface handler { hello: func(); }\nworld api { export handler; }",
+            )
+            .unwrap();
+        let world = resolve.select_world(package, None).unwrap();
+
+        let mut types = TypeSection::new();
+        types.ty().function([], []);
+        let mut functions = FunctionSection::new();
+        functions.function(0);
+        let mut exports = ExportSection::new();
+        exports.export("acme:api/handler#hello", ExportKind::Func, 0);
+        let mut code = CodeSection::new();
+        let mut hello = Function::new([]);
+        hello.instruction(&Instruction::End);
+        code.function(&hello);
+        let mut module = Module::new();
+        module
+            .section(&types)
+            .section(&functions)
+            .section(&exports)
+            .section(&code);
+
+        let mut module = module.finish();
+        wit_component::embed_component_metadata(&mut module, &resolve, world, StringEncoding::UTF8)
+            .unwrap();
+        ComponentEncoder::default()
+            .module(&module)
+            .unwrap()
+            .encode()
+            .unwrap()
+    }
+
+    fn interface(namespace: &str, package: &str, name: &str) -> InterfaceInfo {
+        InterfaceInfo {
+            name: name.to_string(),
+            namespace: namespace.to_string(),
+            package: package.to_string(),
+            version: None,
+            functions: Vec::new(),
+            resources_from: Vec::new(),
+        }
+    }
+
+    fn found(name: &str, imports: Vec<InterfaceInfo>, exports: Vec<InterfaceInfo>) -> Found {
+        Found {
+            name: name.to_string(),
+            path: PathBuf::from(format!("build/{}.wasm", name)),
+            project: None,
+            capability: false,
+            info: Some(ComponentInfo {
+                imports,
+                exports,
+                package: None,
+                claims: None,
+                producers: Default::default(),
+                registry: None,
+                version: None,
+            }),
+            note: None,
+        }
+    }
+
+    #[test]
+    fn scan_finds_components_and_projects() {
+        let dir = temp_dir("scan");
+        std::fs::write(dir.join("http_api_s.wasm"), component()).unwrap();
+        std::fs::write(dir.join("module.wasm"), b"\0asm\x01\0\0\0").unwrap();
+        std::fs::create_dir_all(dir.join("node_modules")).unwrap();
+        std::fs::write(dir.join("node_modules/dep.wasm"), component()).unwrap();
+        std::fs::create_dir_all(dir.join("service")).unwrap();
+        std::fs::write(dir.join("service/wasmcloud.toml"), "name = \"http-api\"").unwrap();
+
+        let mut entries = Vec::new();
+        scan(&dir, &mut entries, &mut HashSet::new()).unwrap();
+        let names: Vec<_> = entries.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["http-api", "http-api-2"]);
+
+        let exports = &entries[0].info.as_ref().unwrap().exports;
+        assert_eq!(exports.len(), 1);
+        assert_eq!(qualified(&exports[0]), "acme:api/handler");
+        assert_eq!(
+            entries[1].project.as_deref(),
+            Some(dir.join("service").as_path())
+        );
+        assert!(entries[1].note.as_deref().unwrap().contains("wash build"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scan_survives_symlink_loops() {
+        let dir = temp_dir("loop");
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("nested/wasmcloud.toml"), "name = \"app\"").unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("loop")).unwrap();
+        std::os::unix::fs::symlink(dir.join("nested"), dir.join("linked")).unwrap();
+
+        let mut entries = Vec::new();
+        scan(&dir, &mut entries, &mut HashSet::new()).unwrap();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn render_suggests_providers_for_unexported_imports() {
+        let entries = [
+            found(
+                "api",
+                vec![
+                    interface("wasi", "keyvalue", "store"),
+                    interface("acme", "db", "query"),
+                    interface("acme", "auth", "verify"),
+                    interface("wasi", "io", "streams"),
+                ],
+                vec![interface("wasi", "http", "incoming-handler")],
+            ),
+            found("db", Vec::new(), vec![interface("acme", "db", "query")]),
+        ];
+        let content = render(Path::new("."), &entries);
+        let config = parse_components(Path::new("components.yaml"), &content).unwrap();
+        let names: Vec<_> = config.entities.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["api", "db", "keyvalue-redis"]);
+
+        assert!(content.contains("  # Exports: wasi:http/incoming-handler\n  - name: api\n"));
+        assert!(content.contains("  # Suggested provider for wasi:keyvalue/store\n"));
+        assert!(content.contains("  # Alternatives: keyvalue-nats"));
+        assert!(content.contains("#   - acme:auth/verify\n"));
+        assert!(!content.contains("#   - acme:db/query"));
+        assert!(!content.contains("#   - wasi:io/streams"));
+        assert!(!content.contains("Suggested provider for wasi:io"));
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::BTreeMap;
+
+use serde_yaml::{Mapping, Value};
+use wadm_types::{ConfigProperty, Manifest, Properties, SecretProperty, TraitProperty};
+
+use crate::cli::Wail;
+use crate::WailPlugin;
+
+/// Recommended labels added to every resource, so `kubectl -l` can select one application
+const PART_OF_LABEL: &str = "app.kubernetes.io/part-of";
+const MANAGED_BY_LABEL: &str = "app.kubernetes.io/managed-by";
+
+impl WailPlugin {
+    /// Renders the manifest as Kubernetes resources for the wasmCloud operator, which serves
+    /// applications as `core.oam.dev/v1beta1` `Application` resources. With `--k8s-stubs`
+    /// the application is followed by a ConfigMap per config and a Secret per secret it
+    /// references, to be filled in before applying.
+    pub(crate) fn kubernetes_documents(args: &Wail, wadm: &Manifest) -> Result<String, ()> {
+        let mut labels = parse_labels(&args.k8s_labels).map_err(|e| {
+            eprintln!("{}", e);
+        })?;
+        labels.insert(PART_OF_LABEL.to_string(), wadm.metadata.name.clone());
+        labels.insert(MANAGED_BY_LABEL.to_string(), "wail".to_string());
+
+        let mut application = serde_yaml::to_value(wadm).map_err(|e| {
+            eprintln!("Failed to serialize manifest: {}", e);
+        })?;
+        if let Some(metadata) = application
+            .get_mut("metadata")
+            .and_then(Value::as_mapping_mut)
+        {
+            set_object_metadata(metadata, args.namespace.as_deref(), &labels);
+        }
+
+        let mut documents = vec![application];
+        if args.k8s_stubs {
+            let (configs, secrets) = references(wadm);
+            for (name, data) in configs {
+                documents.push(resource(
+                    "ConfigMap",
+                    &name,
+                    args.namespace.as_deref(),
+                    &labels,
+                    "data",
+                    data,
+                ));
+            }
+            for (name, keys) in secrets {
+                let data = keys.into_iter().map(|key| (key, String::new())).collect();
+                let mut secret = resource(
+                    "Secret",
+                    &name,
+                    args.namespace.as_deref(),
+                    &labels,
+                    "stringData",
+                    data,
+                );
+                if let Some(secret) = secret.as_mapping_mut() {
+                    secret.insert("type".into(), "Opaque".into());
+                }
+                documents.push(secret);
+            }
+        }
+
+        let documents = documents
+            .iter()
+            .map(serde_yaml::to_string)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| {
+                eprintln!("Failed to serialize Kubernetes resources: {}", e);
+            })?;
+        Ok(documents.join("---\n"))
+    }
+}
+
+/// Parses `--k8s-label key=value` flags
+pub fn parse_labels(flags: &[String]) -> Result<BTreeMap<String, String>, String> {
+    flags
+        .iter()
+        .map(|flag| match flag.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+            _ => Err(format!(
+                "invalid --k8s-label {}, expected <key>=<value>",
+                flag
+            )),
+        })
+        .collect()
+}
+
+fn set_object_metadata(
+    metadata: &mut Mapping,
+    namespace: Option<&str>,
+    labels: &BTreeMap<String, String>,
+) {
+    if let Some(namespace) = namespace {
+        metadata.insert("namespace".into(), namespace.into());
+    }
+    let existing = metadata
+        .entry("labels".into())
+        .or_insert_with(|| Value::Mapping(Mapping::new()));
+    if let Some(existing) = existing.as_mapping_mut() {
+        for (key, value) in labels {
+            existing.insert(key.as_str().into(), value.as_str().into());
+        }
+    }
+}
+
+fn resource(
+    kind: &str,
+    name: &str,
+    namespace: Option<&str>,
+    labels: &BTreeMap<String, String>,
+    data_field: &str,
+    data: BTreeMap<String, String>,
+) -> Value {
+    let mut metadata = Mapping::new();
+    metadata.insert("name".into(), name.into());
+    set_object_metadata(&mut metadata, namespace, labels);
+
+    let mut resource = Mapping::new();
+    resource.insert("apiVersion".into(), "v1".into());
+    resource.insert("kind".into(), kind.into());
+    resource.insert("metadata".into(), Value::Mapping(metadata));
+    resource.insert(
+        data_field.into(),
+        serde_yaml::to_value(data).unwrap_or(Value::Null),
+    );
+    Value::Mapping(resource)
+}
+
+/// Configs (with their inline properties, if any) and secrets (with the fields read from
+/// them) referenced by components and links, keyed by resource name
+type References = (
+    BTreeMap<String, BTreeMap<String, String>>,
+    BTreeMap<String, Vec<String>>,
+);
+
+fn references(wadm: &Manifest) -> References {
+    let mut configs = BTreeMap::new();
+    let mut secrets = BTreeMap::new();
+    let mut add_configs = |list: &[ConfigProperty]| {
+        for config in list {
+            let data: &mut BTreeMap<String, String> =
+                configs.entry(resource_name(&config.name)).or_default();
+            data.extend(config.properties.clone().unwrap_or_default());
+        }
+    };
+    let mut add_secrets = |list: &[SecretProperty]| {
+        for secret in list {
+            let source = &secret.properties;
+            let keys: &mut Vec<String> = secrets.entry(resource_name(&source.key)).or_default();
+            let key = source.field.clone().unwrap_or_else(|| secret.name.clone());
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    };
+
+    for component in &wadm.spec.components {
+        let (config, secret_list) = match &component.properties {
+            Properties::Component { properties } => (&properties.config, &properties.secrets),
+            Properties::Capability { properties } => (&properties.config, &properties.secrets),
+        };
+        add_configs(config);
+        add_secrets(secret_list);
+
+        for component_trait in component.traits.iter().flatten() {
+            let TraitProperty::Link(link) = &component_trait.properties else {
+                continue;
+            };
+            if let Some(source) = &link.source {
+                add_configs(&source.config);
+                add_secrets(&source.secrets);
+            }
+            add_configs(&link.target.config);
+            add_secrets(&link.target.secrets);
+        }
+    }
+    (configs, secrets)
+}
+
+/// A valid Kubernetes resource name for a wasmCloud config or secret key, which may contain
+/// characters like `/` and `_`
+fn resource_name(name: &str) -> String {
+    let name: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .take(253)
+        .collect();
+    name.trim_matches(|c: char| !c.is_ascii_alphanumeric())
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags(flags: &[&str]) -> Vec<String> {
+        flags.iter().map(|flag| flag.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_labels_splits_on_the_first_equals_sign() {
+        let labels = parse_labels(&flags(&[
+            "team=shop",
+            "app.kubernetes.io/tier=a=b",
+            "empty=",
+        ]))
+        .unwrap();
+        assert_eq!(
+            labels,
+            BTreeMap::from([
+                ("app.kubernetes.io/tier".to_string(), "a=b".to_string()),
+                ("empty".to_string(), String::new()),
+                ("team".to_string(), "shop".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_labels_rejects_missing_keys() {
+        for flag in ["team", "=shop"] {
+            assert_eq!(
+                parse_labels(&flags(&[flag])).unwrap_err(),
+                format!("invalid --k8s-label {}, expected <key>=<value>", flag)
+            );
+        }
+    }
+
+    #[test]
+    fn resource_name_is_a_valid_dns_subdomain() {
+        assert_eq!(resource_name("Shop/API_KEY"), "shop-api-key");
+        assert_eq!(resource_name("db.password"), "db.password");
+        assert_eq!(resource_name("_private.key_"), "private.key");
+        assert_eq!(resource_name("--"), "");
+        assert_eq!(resource_name(&"a".repeat(300)), "a".repeat(253));
+        assert_eq!(
+            resource_name(&format!("{}/b", "a".repeat(252))),
+            "a".repeat(252)
+        );
+    }
+}
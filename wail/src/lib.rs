@@ -1,7 +1,7 @@
 #![allow(clippy::missing_safety_doc)]
 wit_bindgen::generate!();
 
-use crate::cli::Wail;
+use crate::cli::{Command, OutputFormat, Wail};
 use crate::exports::wasi::cli::run::Guest as RunGuest;
 use crate::wasi::cli::environment;
 use clap::builder::ValueParser;
@@ -10,26 +10,68 @@ use clap::CommandFactory;
 use clap::FromArgMatches;
 use core::RUNTIME_INTERFACES;
 use exports::wasmcloud::wash::subcommand::{Argument, Guest as SubcommandGuest, Metadata};
+use indexmap::IndexMap;
+use models::components_schema;
+use models::is_json;
 use models::ComponentInfo;
 use models::ComponentsConfig;
 use models::Entity;
-use models::InterfaceInfo;
-use models::PackageInfo;
+use models::EntityKind;
+use models::InterfaceIdentifier;
+use models::PackageRef;
+use models::SecretBackend;
 use std::path::Path;
 use std::path::PathBuf;
+use wadm_types::ConfigProperty;
 use wadm_types::Manifest;
+use wadm_types::Policy;
+use wadm_types::SecretProperty;
+use wadm_types::SecretSourceProperty;
 use wadm_types::Trait;
 
 mod cli;
 mod core;
+// The decoders are public for benches/decode.rs
+pub use core::{decode_wasm_bytes, process_wasm_bytes};
+mod deploy;
+mod explain;
+mod init;
+mod kubernetes;
 mod models;
+mod sbom;
+mod watch;
 
-use core::process_wasm_file;
+use core::claims_annotations;
+use core::format_tools;
+use core::generate_stub;
+use core::is_provider_archive;
+use core::metadata_annotations;
+use core::process_wit_dir;
+use core::run_lints;
+use core::AliasRules;
+use core::ClaimsPolicy;
+use core::ComponentCache;
 use core::ConstructorManifest;
+use core::ImportPolicy;
+use core::LinkConstructor;
+use core::LintConfig;
+use core::MetadataOutput;
+use core::Severity;
+use core::SharedApplications;
+use core::StubMode;
+use core::ValidationReport;
+use core::WashProject;
+use core::ANNOTATION_PREFIX;
+use core::LINT_RULES;
+use core::{lookup_provider, lookup_provider_archive, ImageRef};
 use models::Source;
+use wadm_types::Properties;
+use wadm_types::SharedApplicationComponentProperties;
 
 struct WailPlugin;
 
+// The native build links the library without the component exports
+#[cfg(target_arch = "wasm32")]
 export!(WailPlugin);
 
 impl From<&Arg> for Argument {
@@ -43,71 +85,875 @@ impl From<&Arg> for Argument {
 }
 
 impl WailPlugin {
+    fn run_with_args(args: Vec<String>) -> Result<(), ()> {
+        let cmd = Wail::command();
+
+        // Parse arguments
+        let matches = match cmd.try_get_matches_from(args) {
+            Ok(m) => m,
+            Err(e) => {
+                eprintln!("Error parsing arguments: {}", e);
+                return Err(());
+            }
+        };
+
+        let args = match Wail::from_arg_matches(&matches) {
+            Ok(a) => a,
+            Err(e) => {
+                eprintln!("Error parsing arguments: {}", e);
+                return Err(());
+            }
+        };
+
+        // deploy, check-live and explain run on the inputs
+        if let Some(command) = args.command.as_ref().filter(|c| !c.reads_inputs()) {
+            return Self::run_command(command);
+        }
+
+        // Ensure at least one input is provided
+        if args.wadm.is_none() && args.components.is_none() {
+            eprintln!("Error: Must provide either --wadm or --components or both");
+            return Err(());
+        }
+
+        if args.format != OutputFormat::Kubernetes
+            && (args.namespace.is_some() || !args.k8s_labels.is_empty() || args.k8s_stubs)
+        {
+            eprintln!(
+                "Error: --namespace, --k8s-label and --k8s-stubs require --format kubernetes"
+            );
+            return Err(());
+        }
+        if let Err(e) = kubernetes::parse_labels(&args.k8s_labels) {
+            eprintln!("Error: {}", e);
+            return Err(());
+        }
+
+        if args.wadm.as_deref().is_some_and(is_stdin)
+            && args.components.as_deref().is_some_and(is_stdin)
+        {
+            eprintln!("Error: only one of --wadm and --components can be read from stdin");
+            return Err(());
+        }
+
+        if args.watch {
+            if args
+                .wadm
+                .iter()
+                .chain(&args.components)
+                .any(|p| is_stdin(p))
+            {
+                eprintln!("Error: --watch cannot re-read an input from stdin");
+                return Err(());
+            }
+            if args.command.is_some() {
+                eprintln!("Error: --watch cannot be combined with deploy, check-live or explain");
+                return Err(());
+            }
+            return Self::watch(&args);
+        }
+
+        let mut cache = Self::component_cache(&args);
+        if let Some(Command::Explain {
+            component,
+            interface,
+        }) = &args.command
+        {
+            return Self::explain(&args, &mut cache, component, interface.as_deref());
+        }
+        let wadm = Self::generate(&args, &mut cache)?;
+        match &args.command {
+            Some(Command::Deploy { lattice }) => Self::deploy(&args, lattice, &wadm),
+            Some(Command::CheckLive { lattice }) => Self::check_live(lattice, &wadm),
+            _ => Self::write_output(&args, &wadm),
+        }
+    }
+
+    fn run_command(command: &Command) -> Result<(), ()> {
+        match command {
+            Command::Init { dir, output, force } => Self::init(dir, output, *force),
+            Command::Inspect {
+                path,
+                json,
+                structural,
+            } => Self::inspect(path, *json, *structural),
+            Command::Lints => {
+                for rule in LINT_RULES {
+                    let severity = match rule.default_severity() {
+                        Severity::Off => "off",
+                        Severity::Warn => "warn",
+                        Severity::Error => "error",
+                    };
+                    println!("{:<22} {:<6} {}", rule.id(), severity, rule.description());
+                }
+                Ok(())
+            }
+            Command::Schema { output } => {
+                let schema = components_schema();
+                match output {
+                    Some(path) => {
+                        std::fs::write(path, schema + "\n").map_err(|e| {
+                            eprintln!("Failed to write {}: {}", path.display(), e);
+                        })?;
+                        println!("Wrote components schema to {}", path.display());
+                    }
+                    None => println!("{}", schema),
+                }
+                Ok(())
+            }
+            Command::Deploy { .. } | Command::CheckLive { .. } | Command::Explain { .. } => {
+                unreachable!("commands reading the inputs are run by run_with_args")
+            }
+        }
+    }
+
+    /// Prints the interfaces, metadata and claims decoded from a single component
+    fn inspect(path: &Path, json: bool, structural: bool) -> Result<(), ()> {
+        let bytes = std::fs::read(path).map_err(|e| {
+            eprintln!("Failed to read {}: {}", path.display(), e);
+        })?;
+        let name = path.display().to_string();
+        let info = if structural {
+            decode_wasm_bytes(&name, &bytes)
+        } else {
+            process_wasm_bytes(&name, &bytes)
+        }
+        .map_err(|e| {
+            eprintln!("Failed to decode {}: {}", name, e);
+        })?;
+
+        if json {
+            let content = serde_json::to_string_pretty(&info).map_err(|e| {
+                eprintln!("Failed to serialize {}: {}", name, e);
+            })?;
+            println!("{}", content);
+            return Ok(());
+        }
+
+        println!("Component: {}", name);
+        if let Some(package) = &info.package {
+            println!("Package: {}:{}", package.namespace, package.name);
+        }
+        for (label, interfaces) in [("Imports", &info.imports), ("Exports", &info.exports)] {
+            println!("{}:", label);
+            for interface in interfaces {
+                let version = interface
+                    .version
+                    .as_ref()
+                    .map(|v| format!("@{}", v))
+                    .unwrap_or_default();
+                println!(
+                    "  {}:{}/{}{}",
+                    interface.namespace, interface.package, interface.name, version
+                );
+                for function in &interface.functions {
+                    println!("    {}", function);
+                }
+            }
+        }
+
+        if !info.producers.is_empty() {
+            println!("Producers:");
+            for (field, tools) in &info.producers {
+                println!("  {}: {}", field, format_tools(tools));
+            }
+        }
+        if let Some(registry) = &info.registry {
+            println!("Registry metadata:");
+            if !registry.authors.is_empty() {
+                println!("  Authors: {}", registry.authors.join(", "));
+            }
+            if let Some(description) = &registry.description {
+                println!("  Description: {}", description);
+            }
+            if let Some(license) = &registry.license {
+                println!("  License: {}", license);
+            }
+            for (ty, url) in &registry.links {
+                println!("  {}: {}", ty, url);
+            }
+            if !registry.categories.is_empty() {
+                println!("  Categories: {}", registry.categories.join(", "));
+            }
+        }
+
+        let Some(claims) = &info.claims else {
+            println!("Claims: none, the component is not signed");
+            return Ok(());
+        };
+        println!("Claims:");
+        println!("  Issuer: {}", claims.issuer);
+        println!("  Subject: {}", claims.subject);
+        if let Some(name) = &claims.name {
+            println!("  Name: {}", name);
+        }
+        if let Some(version) = &claims.version {
+            println!("  Version: {}", version);
+        }
+        if let Some(expires) = claims.expires {
+            println!("  Expires: {}", expires);
+        }
+        if !claims.tags.is_empty() {
+            println!("  Tags: {}", claims.tags.join(", "));
+        }
+        println!(
+            "  Signature: {}",
+            if claims.signature_valid {
+                "valid"
+            } else {
+                "INVALID"
+            }
+        );
+        if claims.is_expired() {
+            println!("  Expired or not valid yet");
+        }
+        Ok(())
+    }
+
+    fn component_cache(args: &Wail) -> ComponentCache {
+        let cache = if args.no_cache {
+            ComponentCache::new()
+        } else {
+            ComponentCache::with_dir(args.cache_dir.clone())
+        };
+        cache
+            .structural(args.structural)
+            .pkg_config(args.pkg_config.clone())
+    }
+
+    /// Runs the full pipeline: decode inputs, validate and resolve links, build the manifest
+    fn generate(args: &Wail, cache: &mut ComponentCache) -> Result<Manifest, ()> {
+        let lint_config =
+            LintConfig::load(args.lint_config.as_deref(), &args.lint).map_err(|e| {
+                eprintln!("Invalid lint configuration: {}", e);
+            })?;
+        let claims_policy =
+            ClaimsPolicy::load(args.require_signed, args.issuer_allowlist.as_deref()).map_err(
+                |e| {
+                    eprintln!("Invalid issuer allowlist: {}", e);
+                },
+            )?;
+        if args.stub_unresolved && claims_policy.requires_signing() {
+            eprintln!(
+                "--stub-unresolved cannot be combined with --require-signed or \
+                 --issuer-allowlist: generated stubs are unsigned and would fail the signing policy"
+            );
+            return Err(());
+        }
+
+        let mut constructor = Self::load_inputs(args, cache)?;
+
+        // Validate and resolve links
+        println!("Validating and resolving links...");
+        let mut validation_report = match constructor.validate() {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("Validation failed: {}", e);
+                return Err(());
+            }
+        };
+
+        if args.stub_unresolved && !validation_report.unlinked_interfaces.is_empty() {
+            validation_report = Self::stub_unresolved(
+                &mut constructor,
+                &validation_report,
+                args.stub_mode,
+                &args.stub_dir,
+            )?;
+        }
+
+        run_lints(&constructor, &lint_config, &mut validation_report);
+        claims_policy.check(&constructor, &mut validation_report);
+
+        // If validation produced errors, fail
+        if !validation_report.is_valid {
+            eprintln!("\nValidation errors:");
+            for error in &validation_report.errors {
+                eprintln!("  - {}", error);
+            }
+            return Err(());
+        }
+
+        if !validation_report.exempt_imports.is_empty() {
+            println!("\nImports left unlinked:");
+            for exempt in &validation_report.exempt_imports {
+                println!(
+                    "  - {}: {} ({})",
+                    exempt.component, exempt.interface, exempt.policy
+                );
+            }
+        }
+
+        if !validation_report.aliased_links.is_empty() {
+            println!("\nLinks matched through interface aliases:");
+            for aliased in &validation_report.aliased_links {
+                println!(
+                    "  - {} -> {}: {} as {} (rule {})",
+                    aliased.link.pre_component_id,
+                    aliased
+                        .link
+                        .post_component_id
+                        .as_deref()
+                        .unwrap_or_default(),
+                    aliased.import,
+                    aliased.export,
+                    aliased.rule
+                );
+            }
+        }
+
+        // Print warnings if any
+        if !validation_report.warnings.is_empty() {
+            println!("\nWarnings:");
+            for warning in &validation_report.warnings {
+                println!("  - {}", warning);
+            }
+        }
+
+        if let Some(sbom) = &args.sbom {
+            Self::write_sbom(&constructor, args, sbom)?;
+        }
+
+        // Transform to final WADM
+        Ok(Self::transform_to_wadm(
+            constructor,
+            args.name.clone(),
+            args.version.clone(),
+            args.description.clone(),
+            args.component_metadata,
+        ))
+    }
+
+    /// Builds the constructor from the alias rules, shared applications, components file and
+    /// WADM input, before links are resolved
+    fn load_inputs(args: &Wail, cache: &mut ComponentCache) -> Result<ConstructorManifest, ()> {
+        // Start with an empty constructor
+        let mut constructor = ConstructorManifest::new();
+
+        if let Some(path) = &args.alias_rules {
+            AliasRules::load(path)
+                .and_then(|aliases| constructor.add_aliases(&aliases))
+                .map_err(|e| {
+                    eprintln!("Invalid alias rules: {}", e);
+                })?;
+        }
+
+        let mut shared =
+            SharedApplications::load(&args.shared_manifests, args.manifest_registry.as_deref())
+                .map_err(|e| {
+                    eprintln!("Failed to load shared application manifests: {:#}", e);
+                })?;
+        if let Some(lattice) = args.command.as_ref().and_then(Command::lattice) {
+            Self::add_deployed_shared(lattice, &mut shared)?;
+        }
+
+        // Process components.yaml if provided
+        if let Some(components_path) = &args.components {
+            println!(
+                "Processing components from: {}",
+                input_name(components_path)
+            );
+            if Self::process_components(
+                &mut constructor,
+                components_path,
+                args.env.as_deref(),
+                cache,
+                &shared,
+            )
+            .is_err()
+            {
+                eprintln!("Failed to process components");
+                return Err(());
+            }
+        }
+
+        // Process WADM if provided
+        if let Some(wadm_path) = &args.wadm {
+            println!("Processing WADM manifest from: {}", input_name(wadm_path));
+
+            let input_content = match read_input(wadm_path) {
+                Ok(content) => content,
+                Err(e) => {
+                    eprintln!("Failed to read WADM file: {}", e);
+                    return Err(());
+                }
+            };
+
+            let parsed = if is_json(&input_content) {
+                serde_json::from_str(&input_content).map_err(|e| e.to_string())
+            } else {
+                serde_yaml::from_str(&input_content).map_err(|e| e.to_string())
+            };
+            let wadm: Manifest = match parsed {
+                Ok(manifest) => manifest,
+                Err(e) => {
+                    eprintln!("Failed to parse WADM manifest: {}", e);
+                    return Err(());
+                }
+            };
+
+            if let Err(e) = constructor.merge_wadm(&wadm, cache, &shared) {
+                eprintln!("Failed to merge WADM manifest: {}", e);
+                return Err(());
+            }
+        }
+
+        Ok(constructor)
+    }
+
+    fn write_output(args: &Wail, wadm: &Manifest) -> Result<(), ()> {
+        let output_content = match args.format {
+            OutputFormat::Wadm => match serde_yaml::to_string(wadm) {
+                Ok(content) => content,
+                Err(e) => {
+                    eprintln!("Failed to serialize manifest: {}", e);
+                    return Err(());
+                }
+            },
+            OutputFormat::Kubernetes => Self::kubernetes_documents(args, wadm)?,
+        };
+
+        match &args.output {
+            Some(output_path) => {
+                std::fs::write(output_path, output_content).map_err(|e| {
+                    eprintln!("Failed to write {}: {}", output_path.display(), e);
+                })?;
+                println!("Wrote manifest to {}", output_path.display());
+            }
+            None => {
+                println!("\n---");
+                println!("{}", output_content);
+            }
+        }
+
+        Ok(())
+    }
+
     fn process_components(
         constructor: &mut ConstructorManifest,
         components_path: &Path,
+        env: Option<&str>,
+        cache: &mut ComponentCache,
+        shared: &SharedApplications,
     ) -> Result<(), ()> {
         let components_config: ComponentsConfig = {
-            let content = std::fs::read_to_string(components_path).map_err(|e| {
+            let content = read_input(components_path).map_err(|e| {
                 eprintln!("Failed to read components file: {}", e);
             })?;
-            serde_yaml::from_str(&content).map_err(|e| {
-                eprintln!("Failed to parse components file: {}", e);
+            let path = input_name(components_path);
+            let vars = std::env::vars().collect();
+            ComponentsConfig::interpolated(Path::new(&path), &content, env, &vars).map_err(|e| {
+                eprintln!("Failed to parse components file\n{}", e);
             })?
         };
+        let components_config = components_config.resolve(env).map_err(|e| {
+            eprintln!("Failed to apply environment: {}", e);
+        })?;
+        if let Some(env) = env {
+            println!("Applied environment {}", env);
+        }
 
+        for backend in &components_config.secret_backends {
+            constructor.add_policy(secret_policy(backend));
+        }
+        constructor
+            .add_aliases(&components_config.aliases)
+            .map_err(|e| {
+                eprintln!("Invalid alias rules: {}", e);
+            })?;
+
+        // Decode every component up front so large applications decode in parallel
+        let files: Vec<_> = components_config
+            .entities
+            .iter()
+            .filter(|entity| entity.kind != Some(EntityKind::Capability))
+            .filter_map(|entity| match &entity.source {
+                Some(Source::File { path }) => Some((entity.name.clone(), path.clone())),
+                Some(Source::Project { project }) => WashProject::load(project)
+                    .ok()
+                    .filter(|project| !project.is_provider())
+                    .and_then(|project| project.artifact())
+                    .map(|path| (entity.name.clone(), path)),
+                None => Some((entity.name.clone(), entity.get_source())),
+                Some(_) => None,
+            })
+            .filter(|(_, path)| !is_provider_archive(path))
+            .collect();
+        cache.prefetch(&files);
+
+        let input = input_name(components_path);
         for entity in &components_config.entities {
-            Self::process_entity(constructor, entity)?;
+            constructor.declare(&input, &entity.name);
+            Self::process_entity(constructor, entity, cache, shared)?;
+            Self::apply_entity_config(constructor, entity)?;
         }
 
+        // Links are applied once every entity is known so targets can be checked
+        for entity in &components_config.entities {
+            Self::apply_entity_links(constructor, &input, entity)?;
+            Self::apply_import_policies(
+                constructor,
+                Some(&entity.name),
+                &entity.optional,
+                ImportPolicy::Optional,
+            )?;
+            Self::apply_import_policies(
+                constructor,
+                Some(&entity.name),
+                &entity.external,
+                ImportPolicy::External,
+            )?;
+        }
+        Self::apply_import_policies(
+            constructor,
+            None,
+            &components_config.ignore_imports,
+            ImportPolicy::Ignored,
+        )?;
+
         Ok(())
     }
 
-    fn process_entity(constructor: &mut ConstructorManifest, entity: &Entity) -> Result<(), ()> {
+    fn process_entity(
+        constructor: &mut ConstructorManifest,
+        entity: &Entity,
+        cache: &mut ComponentCache,
+        shared: &SharedApplications,
+    ) -> Result<(), ()> {
         match &entity.source {
-            Some(Source::File { path }) => Self::process_file_entity(constructor, entity, path),
+            Some(Source::File { path }) => {
+                Self::process_file_entity(constructor, entity, path, cache)
+            }
             Some(Source::OCI { reference }) => {
-                println!("Processing OCI component: {} at {}", entity.name, reference);
-                // TODO - add oci handling and known interfaces
-                // Handle known capability providers
-                if reference.contains("wasmcloud/http-server") {
-                    // Create a ComponentInfo for the HTTP server
-                    let info = ComponentInfo {
-                        imports: vec![
-                            // HTTP server IMPORTS incoming-handler (which our component exports)
-                            InterfaceInfo {
-                                name: "incoming-handler".to_string(),
-                                namespace: "wasi".to_string(),
-                                package: "http".to_string(),
-                            },
-                        ],
-                        exports: vec![
-                            // HTTP server EXPORTS outgoing-handler (which our component imports)
-                            InterfaceInfo {
-                                name: "outgoing-handler".to_string(),
-                                namespace: "wasi".to_string(),
-                                package: "http".to_string(),
-                            },
-                        ],
-                        package: Some(PackageInfo {
-                            namespace: "wasmcloud".to_string(),
-                            name: "httpserver".to_string(),
-                        }),
-                    };
-                    if let Err(e) = constructor.merge_component_info(
+                Self::process_oci_entity(constructor, entity, reference, cache)
+            }
+            Some(Source::Application {
+                application,
+                component,
+            }) => {
+                let reference = SharedApplicationComponentProperties {
+                    name: application.clone(),
+                    component: component.clone(),
+                };
+                let (info, kind) = shared.resolve(&reference, cache).map_err(|e| {
+                    eprintln!(
+                        "Failed to resolve shared component {}: {:#}",
+                        entity.name, e
+                    );
+                })?;
+                constructor
+                    .merge_shared_component(
                         entity.name.clone(),
                         info,
-                        PathBuf::from(reference),
-                    ) {
+                        reference,
+                        entity.kind.unwrap_or(kind),
+                    )
+                    .map_err(|e| {
                         eprintln!("Failed to merge component info: {}", e);
-                        return Err(());
+                    })
+            }
+            Some(Source::Project { project }) => {
+                Self::process_project_entity(constructor, entity, project, cache)
+            }
+            Some(Source::Url { url, sha256 }) => {
+                Self::process_url_entity(constructor, entity, url, sha256, cache)
+            }
+            Some(Source::Package { package }) => {
+                Self::process_package_entity(constructor, entity, package, cache)
+            }
+            None => {
+                let default_path = entity.get_source();
+                Self::process_file_entity(constructor, entity, &default_path, cache)
+            }
+        }
+    }
+
+    /// Adds a wash project through its build artifact. Before the project is built, its WIT
+    /// world stands in for the component and the manifest points at the expected artifact.
+    fn process_project_entity(
+        constructor: &mut ConstructorManifest,
+        entity: &Entity,
+        dir: &Path,
+        cache: &mut ComponentCache,
+    ) -> Result<(), ()> {
+        cache.track(&WashProject::manifest_path(dir));
+        let project = WashProject::load(dir).map_err(|e| {
+            eprintln!("Failed to load wash project of {}: {:#}", entity.name, e);
+        })?;
+
+        match project.artifact() {
+            Some(artifact) => Self::process_file_entity(constructor, entity, &artifact, cache)?,
+            None => {
+                let expected = project.expected_artifact();
+                cache.track(&expected);
+                if project.is_provider() || entity.kind == Some(EntityKind::Capability) {
+                    Self::process_archive_entity(constructor, entity, &expected)?;
+                } else {
+                    println!(
+                        "{} has not been built yet, using its WIT world until {} exists",
+                        entity.name,
+                        expected.display()
+                    );
+                    for file in project.wit_files() {
+                        cache.track(&file);
                     }
+                    let info =
+                        process_wit_dir(&entity.name, &project.wit_dir(), project.wit_world())
+                            .map_err(|e| {
+                                eprintln!(
+                                    "Failed to read the WIT world of {}: {:#}",
+                                    entity.name, e
+                                );
+                            })?;
+                    constructor
+                        .merge_component_info(
+                            entity.name.clone(),
+                            info,
+                            expected,
+                            EntityKind::Component,
+                        )
+                        .map_err(|e| {
+                            eprintln!("Failed to merge component interface info: {}", e);
+                        })?;
                 }
-                Ok(())
             }
+        }
+
+        if let Some(info) = constructor.component_interfaces.get_mut(&entity.name) {
+            info.version = project.version;
+        }
+        Ok(())
+    }
+
+    /// Adds a component downloaded over HTTP(S), decoded from the verified local copy. The
+    /// manifest keeps the URL as the image.
+    fn process_url_entity(
+        constructor: &mut ConstructorManifest,
+        entity: &Entity,
+        url: &str,
+        sha256: &str,
+        cache: &mut ComponentCache,
+    ) -> Result<(), ()> {
+        let path = cache.download(&entity.name, url, sha256).map_err(|e| {
+            eprintln!("Failed to download {}: {:#}", entity.name, e);
+        })?;
+        constructor
+            .local_copies
+            .insert(entity.name.clone(), path.clone());
+        if entity.kind == Some(EntityKind::Capability) || is_provider_archive(&path) {
+            return Self::process_archive_entity(constructor, entity, Path::new(url));
+        }
+
+        let info = cache.process_wasm_file(&entity.name, &path).map_err(|e| {
+            eprintln!("Error: Failed to process WASM file {}: {}", url, e);
+        })?;
+        constructor
+            .merge_component_info(
+                entity.name.clone(),
+                info,
+                PathBuf::from(url),
+                EntityKind::Component,
+            )
+            .map_err(|e| {
+                eprintln!("Failed to merge component interface info: {}", e);
+            })
+    }
+
+    /// Adds a component or WIT package pulled from a wasm-pkg registry. The manifest
+    /// references the OCI image the registry stores it in.
+    fn process_package_entity(
+        constructor: &mut ConstructorManifest,
+        entity: &Entity,
+        package: &PackageRef,
+        cache: &mut ComponentCache,
+    ) -> Result<(), ()> {
+        let pulled = cache.pull_package(&entity.name, package).map_err(|e| {
+            eprintln!("Failed to pull {} for {}: {:#}", package, entity.name, e);
+        })?;
+        let info = cache
+            .process_wasm_file(&entity.name, &pulled.path)
+            .map_err(|e| {
+                eprintln!("Error: Failed to process {}: {}", package, e);
+            })?;
+        constructor
+            .local_copies
+            .insert(entity.name.clone(), pulled.path.clone());
+        constructor
+            .merge_component_info(
+                entity.name.clone(),
+                info,
+                PathBuf::from(pulled.image),
+                entity.kind.unwrap_or(EntityKind::Component),
+            )
+            .map_err(|e| {
+                eprintln!("Failed to merge component interface info: {}", e);
+            })
+    }
+
+    fn apply_entity_config(
+        constructor: &mut ConstructorManifest,
+        entity: &Entity,
+    ) -> Result<(), ()> {
+        if let Some(instances) = entity.instances {
+            constructor
+                .set_instances(&entity.name, instances)
+                .map_err(|e| {
+                    eprintln!("Failed to scale {}: {}", entity.name, e);
+                })?;
+        }
+
+        if entity.config.is_empty() && entity.secrets.is_empty() {
+            return Ok(());
+        }
+
+        let config = entity
+            .config
+            .iter()
+            .map(|config| ConfigProperty {
+                name: config.name.clone(),
+                properties: config.properties.clone(),
+            })
+            .collect();
+        let secrets = entity
+            .secrets
+            .iter()
+            .map(|secret| SecretProperty {
+                name: secret.name.clone(),
+                properties: SecretSourceProperty {
+                    policy: secret.backend.clone(),
+                    key: secret.key.clone(),
+                    field: secret.field.clone(),
+                    version: secret.version.clone(),
+                },
+            })
+            .collect();
+
+        constructor
+            .apply_config(&entity.name, config, secrets)
+            .map_err(|e| {
+                eprintln!("Failed to apply config for {}: {}", entity.name, e);
+            })
+    }
+
+    fn apply_entity_links(
+        constructor: &mut ConstructorManifest,
+        input: &str,
+        entity: &Entity,
+    ) -> Result<(), ()> {
+        for link in &entity.links {
+            let Some(interface) = InterfaceIdentifier::parse(&link.interface) else {
+                eprintln!(
+                    "Invalid interface '{}' in links of {}: expected namespace:package/interface",
+                    link.interface, entity.name
+                );
+                return Err(());
+            };
+            constructor
+                .apply_link(
+                    input,
+                    &entity.name,
+                    &interface.namespace,
+                    &interface.package,
+                    &[interface.name],
+                    link.name.as_deref(),
+                    link.target.clone(),
+                )
+                .map_err(|e| {
+                    eprintln!("Failed to apply link for {}: {}", entity.name, e);
+                })?;
+        }
+        Ok(())
+    }
+
+    fn apply_import_policies(
+        constructor: &mut ConstructorManifest,
+        component: Option<&str>,
+        interfaces: &[String],
+        policy: ImportPolicy,
+    ) -> Result<(), ()> {
+        for interface in interfaces {
+            constructor
+                .set_import_policy(component, interface, policy)
+                .map_err(|e| {
+                    eprintln!("Failed to apply {} import: {}", policy, e);
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Adds an OCI image. Its kind is declared, known from the provider catalog, or else
+    /// inferred from the media types of its OCI manifest.
+    fn process_oci_entity(
+        constructor: &mut ConstructorManifest,
+        entity: &Entity,
+        reference: &str,
+        cache: &mut ComponentCache,
+    ) -> Result<(), ()> {
+        println!("Processing OCI component: {} at {}", entity.name, reference);
+        let provider = lookup_provider(reference);
+        let kind = match (entity.kind, provider) {
+            (Some(kind), _) => kind,
+            (None, Some(_)) => EntityKind::Capability,
+            (None, None) => Self::infer_image_kind(entity, reference, cache)?,
+        };
+
+        let info = match provider {
+            Some(provider) => provider.component_info(),
             None => {
-                let default_path = entity.get_source();
-                Self::process_file_entity(constructor, entity, &default_path)
+                println!(
+                    "No interface information known for {}, adding it without links",
+                    reference
+                );
+                ComponentInfo {
+                    imports: Vec::new(),
+                    exports: Vec::new(),
+                    package: None,
+                    claims: None,
+                    producers: Default::default(),
+                    registry: None,
+                    version: None,
+                }
+            }
+        };
+
+        constructor
+            .merge_component_info(entity.name.clone(), info, PathBuf::from(reference), kind)
+            .map_err(|e| {
+                eprintln!("Failed to merge component info: {}", e);
+            })
+    }
+
+    /// The kind the OCI manifest of `reference` declares, defaulting to a component when
+    /// the media types are unknown. Failing to reach the registry is an error since guessing
+    /// wrong emits a provider as a component.
+    fn infer_image_kind(
+        entity: &Entity,
+        reference: &str,
+        cache: &mut ComponentCache,
+    ) -> Result<EntityKind, ()> {
+        let Some(image) = ImageRef::parse(reference) else {
+            eprintln!(
+                "Cannot parse the OCI reference {} of {}; set `kind` to skip the lookup",
+                reference, entity.name
+            );
+            return Err(());
+        };
+        match cache.image_kind(&entity.name, &image) {
+            Ok(Some(kind)) => Ok(kind),
+            Ok(None) => {
+                println!(
+                    "The OCI manifest of {} has unknown media types, treating {} as a component",
+                    reference, entity.name
+                );
+                Ok(EntityKind::Component)
+            }
+            Err(e) => {
+                eprintln!(
+                    "Cannot inspect the OCI manifest of {} for {}: {:#}\n\
+                     Set `kind` to skip the lookup",
+                    reference, entity.name, e
+                );
+                Err(())
             }
         }
     }
@@ -116,7 +962,12 @@ impl WailPlugin {
         constructor: &mut ConstructorManifest,
         entity: &Entity,
         path: &Path,
+        cache: &mut ComponentCache,
     ) -> Result<(), ()> {
+        if entity.kind == Some(EntityKind::Capability) || is_provider_archive(path) {
+            return Self::process_archive_entity(constructor, entity, path);
+        }
+
         println!(
             "Processing WASM component: {} at {}",
             entity.name,
@@ -124,7 +975,7 @@ impl WailPlugin {
         );
 
         // Get interfaces from WASM file (Source of Truth)
-        match process_wasm_file(&entity.name, path) {
+        match cache.process_wasm_file(&entity.name, path) {
             Ok(component_info) => {
                 println!("Got component interface info:");
                 println!("  Imports: {:?}", component_info.imports);
@@ -132,7 +983,12 @@ impl WailPlugin {
 
                 // Add ALL imports and exports from the WASM file
                 constructor
-                    .merge_component_info(entity.name.clone(), component_info, path.to_path_buf())
+                    .merge_component_info(
+                        entity.name.clone(),
+                        component_info,
+                        path.to_path_buf(),
+                        EntityKind::Component,
+                    )
                     .map_err(|e| {
                         eprintln!("Failed to merge component interface info: {}", e);
                     })?;
@@ -149,28 +1005,231 @@ impl WailPlugin {
         }
     }
 
+    /// Adds a capability provider shipped as a provider archive. Its interfaces come from
+    /// the provider catalog since archives carry native binaries rather than WIT.
+    fn process_archive_entity(
+        constructor: &mut ConstructorManifest,
+        entity: &Entity,
+        path: &Path,
+    ) -> Result<(), ()> {
+        println!(
+            "Processing capability provider: {} at {}",
+            entity.name,
+            path.display()
+        );
+
+        let info = match lookup_provider_archive(&path.to_string_lossy()) {
+            Some(provider) => provider.component_info(),
+            None => {
+                println!(
+                    "No interface information known for provider {}, adding it without links",
+                    entity.name
+                );
+                ComponentInfo {
+                    imports: Vec::new(),
+                    exports: Vec::new(),
+                    package: None,
+                    claims: None,
+                    producers: Default::default(),
+                    registry: None,
+                    version: None,
+                }
+            }
+        };
+
+        constructor
+            .merge_component_info(
+                entity.name.clone(),
+                info,
+                path.to_path_buf(),
+                EntityKind::Capability,
+            )
+            .map_err(|e| {
+                eprintln!("Failed to merge component info: {}", e);
+            })
+    }
+
+    /// Generates a stub provider for every interface left unlinked by validation, adds the
+    /// stubs to the manifest and validates again
+    fn stub_unresolved(
+        constructor: &mut ConstructorManifest,
+        report: &ValidationReport,
+        mode: StubMode,
+        stub_dir: &Path,
+    ) -> Result<ValidationReport, ()> {
+        std::fs::create_dir_all(stub_dir).map_err(|e| {
+            eprintln!(
+                "Failed to create stub directory {}: {}",
+                stub_dir.display(),
+                e
+            );
+        })?;
+
+        // One stub per package version, since wasmCloud links whole packages. Each interface
+        // is described by the first of its importers with a known WASM file.
+        // Interfaces keyed by namespace, package and version, with the file describing each
+        type Interfaces = Vec<(String, Option<PathBuf>)>;
+        let mut packages: IndexMap<(String, String, Option<String>), Interfaces> = IndexMap::new();
+        for unlinked in &report.unlinked_interfaces {
+            let id = &unlinked.interface.identifier;
+            let version = constructor
+                .component_interfaces
+                .get(&unlinked.component)
+                .and_then(|info| {
+                    info.imports.iter().find(|import| {
+                        import.namespace == id.namespace
+                            && import.package == id.package
+                            && import.name == id.name
+                    })
+                })
+                .and_then(|import| import.version.clone());
+            let path = Self::component_file(constructor, &unlinked.component);
+            let interfaces = packages
+                .entry((id.namespace.clone(), id.package.clone(), version))
+                .or_default();
+            match interfaces.iter_mut().find(|(name, _)| *name == id.name) {
+                Some((_, known)) => {
+                    if known.is_none() {
+                        *known = path;
+                    }
+                }
+                None => interfaces.push((id.name.clone(), path)),
+            }
+        }
+
+        for ((namespace, package, version), interfaces) in &packages {
+            // Versions only tell stubs apart when a package is stubbed in several
+            let versions = packages
+                .keys()
+                .filter(|(ns, pkg, _)| ns == namespace && pkg == package)
+                .count();
+            let stub_name = match version {
+                Some(version) if versions > 1 => {
+                    format!(
+                        "stub-{}-{}-{}",
+                        namespace,
+                        package,
+                        version.replace('.', "-")
+                    )
+                }
+                _ => format!("stub-{}-{}", namespace, package),
+            };
+            let names: Vec<_> = interfaces.iter().map(|(name, _)| name.as_str()).collect();
+            let qualified = format!(
+                "{}:{}/{{{}}}{}",
+                namespace,
+                package,
+                names.join(", "),
+                version
+                    .as_ref()
+                    .map(|v| format!("@{}", v))
+                    .unwrap_or_default()
+            );
+            if constructor.component_exists(&stub_name) {
+                continue;
+            }
+
+            let mut importers = Vec::new();
+            for (name, path) in interfaces {
+                let Some(path) = path else {
+                    eprintln!(
+                        "Cannot stub {}:{}/{}: no WASM file known for any component importing it",
+                        namespace, package, name
+                    );
+                    return Err(());
+                };
+                importers.push((name.clone(), path.clone()));
+            }
+
+            let bytes = generate_stub(namespace, package, version.as_deref(), &importers, mode)
+                .map_err(|e| {
+                    eprintln!("Failed to generate stub for {}: {:#}", qualified, e);
+                })?;
+
+            let stub_path = stub_dir.join(format!("{}.wasm", stub_name));
+            std::fs::write(&stub_path, &bytes).map_err(|e| {
+                eprintln!("Failed to write stub {}: {}", stub_path.display(), e);
+            })?;
+            println!(
+                "Generated stub {} for {} at {}",
+                stub_name,
+                qualified,
+                stub_path.display()
+            );
+
+            let info = process_wasm_bytes(&stub_name, &bytes).map_err(|e| {
+                eprintln!("Failed to decode generated stub {}: {}", stub_name, e);
+            })?;
+            constructor
+                .merge_component_info(stub_name, info, stub_path, EntityKind::Component)
+                .map_err(|e| {
+                    eprintln!("Failed to merge stub component info: {}", e);
+                })?;
+        }
+
+        println!("Re-validating with stub components...");
+        constructor.validate().map_err(|e| {
+            eprintln!("Validation failed: {}", e);
+        })
+    }
+
+    /// The local WASM file of `component`, if it is a component decoded from one
+    fn component_file(constructor: &ConstructorManifest, component: &str) -> Option<PathBuf> {
+        let entry = constructor.get_component(component)?;
+        if !matches!(entry.properties, Properties::Component { .. }) {
+            return None;
+        }
+        constructor.local_file(component)
+    }
+
     fn transform_to_wadm(
         input: ConstructorManifest,
         name: String,
         version: String,
         description: String,
+        component_metadata: Option<MetadataOutput>,
     ) -> Manifest {
+        let mut component_annotations = Vec::new();
+        let mut component_labels = Vec::new();
+        for component in &input.spec.components {
+            let Some(info) = input.component_interfaces.get(&component.name) else {
+                continue;
+            };
+            if let Some(version) = &info.version {
+                component_annotations.push((
+                    format!("{}/{}.version", ANNOTATION_PREFIX, component.name),
+                    version.clone(),
+                ));
+            }
+            if let Some(claims) = &info.claims {
+                component_annotations.extend(claims_annotations(&component.name, claims));
+            }
+            if let Some(output) = component_metadata {
+                let entries = metadata_annotations(&component.name, info, output);
+                match output {
+                    MetadataOutput::Annotations => component_annotations.extend(entries),
+                    MetadataOutput::Labels => component_labels.extend(entries),
+                }
+            }
+        }
         let mut components = input.spec.components;
 
         for component in &mut components {
             // Only include non-WASI link constructors
-            let component_links: Vec<Trait> = input
-                .link_constructors
-                .iter()
-                .filter(|c| c.pre_component_id == component.name)
-                .filter(|c| {
-                    !(c.namespace == "wasi"
-                        && RUNTIME_INTERFACES
-                            .iter()
-                            .any(|(_, pkg, name)| pkg == &c.package && name == &c.interfaces[0]))
-                })
-                .map(|c| c.to_wadm_link())
-                .collect();
+            let component_links: Vec<Trait> = LinkConstructor::to_wadm_links(
+                input
+                    .link_constructors
+                    .iter()
+                    .filter(|c| c.pre_component_id == component.name)
+                    // Optional and external imports can stay unlinked
+                    .filter(|c| c.post_component_id.is_some())
+                    .filter(|c| {
+                        !(c.namespace == "wasi"
+                            && RUNTIME_INTERFACES.iter().any(|(_, pkg, name)| {
+                                pkg == &c.package && name == &c.interfaces[0]
+                            }))
+                    }),
+            );
 
             let mut traits = component.traits.clone().unwrap_or_default();
             traits.extend(component_links);
@@ -184,6 +1243,14 @@ impl WailPlugin {
             description,
         );
 
+        let mut metadata = input.metadata.unwrap_or_else(|| wadm_types::Metadata {
+            name,
+            annotations,
+            labels: Default::default(),
+        });
+        metadata.annotations.extend(component_annotations);
+        metadata.labels.extend(component_labels);
+
         Manifest {
             api_version: input
                 .api_version
@@ -191,11 +1258,7 @@ impl WailPlugin {
             kind: input
                 .kind
                 .unwrap_or_else(|| wadm_types::APPLICATION_KIND.to_string()),
-            metadata: input.metadata.unwrap_or_else(|| wadm_types::Metadata {
-                name,
-                annotations,
-                labels: Default::default(),
-            }),
+            metadata,
             spec: wadm_types::Specification {
                 components,
                 policies: input.spec.policies,
@@ -204,118 +1267,35 @@ impl WailPlugin {
     }
 }
 
+/// The secret policy for a backend declared in components.yaml
+fn secret_policy(backend: &SecretBackend) -> Policy {
+    let mut properties = backend.properties.clone();
+    properties.insert(
+        "backend".to_string(),
+        backend
+            .backend
+            .clone()
+            .unwrap_or_else(|| backend.name.clone()),
+    );
+    Policy {
+        name: backend.name.clone(),
+        properties,
+        policy_type: wadm_types::SECRET_POLICY_TYPE.to_string(),
+    }
+}
+
 impl RunGuest for WailPlugin {
     fn run() -> Result<(), ()> {
-        let args = environment::get_arguments();
-        let cmd = Wail::command();
-
-        // Parse arguments
-        let matches = match cmd.try_get_matches_from(args) {
-            Ok(m) => m,
-            Err(e) => {
-                eprintln!("Error parsing arguments: {}", e);
-                return Err(());
-            }
-        };
-
-        let args = match Wail::from_arg_matches(&matches) {
-            Ok(a) => a,
-            Err(e) => {
-                eprintln!("Error parsing arguments: {}", e);
-                return Err(());
-            }
-        };
-
-        // Ensure at least one input is provided
-        if args.wadm.is_none() && args.components.is_none() {
-            eprintln!("Error: Must provide either --wadm or --components or both");
-            return Err(());
-        }
-
-        // Start with an empty constructor
-        let mut constructor = ConstructorManifest::new();
-
-        // Process components.yaml if provided
-        if let Some(components_path) = &args.components {
-            println!("Processing components from: {}", components_path.display());
-            if let Err(_) = Self::process_components(&mut constructor, components_path) {
-                eprintln!("Failed to process components");
-                return Err(());
-            }
-        }
-
-        // Process WADM if provided
-        if let Some(wadm_path) = &args.wadm {
-            println!("Processing WADM manifest from: {}", wadm_path.display());
-
-            let input_content = match std::fs::read_to_string(wadm_path) {
-                Ok(content) => content,
-                Err(e) => {
-                    eprintln!("Failed to read WADM file: {}", e);
-                    return Err(());
-                }
-            };
-
-            let wadm: Manifest = match serde_yaml::from_str(&input_content) {
-                Ok(manifest) => manifest,
-                Err(e) => {
-                    eprintln!("Failed to parse WADM manifest: {}", e);
-                    return Err(());
-                }
-            };
-
-            if let Err(e) = constructor.merge_wadm(&wadm) {
-                eprintln!("Failed to merge WADM manifest: {}", e);
-                return Err(());
-            }
-        }
-
-        // Validate and resolve links
-        println!("Validating and resolving links...");
-        let validation_report = match constructor.validate() {
-            Ok(report) => report,
-            Err(e) => {
-                eprintln!("Validation failed: {}", e);
-                return Err(());
-            }
-        };
-
-        // If validation produced errors, fail
-        if !validation_report.is_valid {
-            eprintln!("\nValidation errors:");
-            for error in &validation_report.errors {
-                eprintln!("  - {}", error);
-            }
-            return Err(());
-        }
-
-        // Print warnings if any
-        if !validation_report.warnings.is_empty() {
-            println!("\nWarnings:");
-            for warning in &validation_report.warnings {
-                println!("  - {}", warning);
-            }
-        }
-
-        // Transform to final WADM
-        let wadm = Self::transform_to_wadm(constructor, args.name, args.version, args.description);
-
-        // Output result
-        let output_content = match serde_yaml::to_string(&wadm) {
-            Ok(content) => content,
-            Err(e) => {
-                eprintln!("Failed to serialize manifest: {}", e);
-                return Err(());
-            }
-        };
-
-        println!("\n---");
-        println!("{}", output_content);
-
-        Ok(())
+        Self::run_with_args(environment::get_arguments())
     }
 }
 
+/// Entry point for the native build, taking the full argument list including the binary name
+#[allow(clippy::result_unit_err)]
+pub fn run_cli(args: Vec<String>) -> Result<(), ()> {
+    WailPlugin::run_with_args(args)
+}
+
 impl SubcommandGuest for WailPlugin {
     fn register() -> Metadata {
         let cmd = Wail::command();
@@ -344,6 +1324,41 @@ impl SubcommandGuest for WailPlugin {
     }
 }
 
+/// The path that reads an input from stdin instead of a file
+const STDIN: &str = "-";
+
+fn is_stdin(path: &Path) -> bool {
+    path == Path::new(STDIN)
+}
+
+/// How an input is named in messages
+fn input_name(path: &Path) -> String {
+    if is_stdin(path) {
+        "<stdin>".to_string()
+    } else {
+        path.display().to_string()
+    }
+}
+
+/// Reads an input file, or stdin when the path is `-`
+fn read_input(path: &Path) -> std::io::Result<String> {
+    if !is_stdin(path) {
+        return std::fs::read_to_string(path);
+    }
+    use std::io::Read;
+
+    let mut content = String::new();
+    // Inside wash stdin is the plugin's WASI input stream
+    #[cfg(target_arch = "wasm32")]
+    {
+        let mut stdin = crate::wasi::cli::stdin::get_stdin();
+        InputStreamReader::from(&mut stdin).read_to_string(&mut content)?;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    std::io::stdin().read_to_string(&mut content)?;
+    Ok(content)
+}
+
 pub struct InputStreamReader<'a> {
     stream: &'a mut crate::wasi::io::streams::InputStream,
 }
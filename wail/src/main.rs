@@ -0,0 +1,5 @@
+fn main() {
+    if wail::run_cli(std::env::args().collect()).is_err() {
+        std::process::exit(1);
+    }
+}
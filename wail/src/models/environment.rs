@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+use super::components::{scalar_format, EntityConfig, EntityLink, EntitySecret};
+use super::{parse_components, ComponentsConfig, Entity, EntityKind, Source};
+
+/// Changes applied on top of the base entities when an environment is selected
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Environment {
+    /// Patches for entities, matched by name. Entities not in the base file are added.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entities: Vec<EntityOverlay>,
+}
+
+/// Fields of an entity an environment can override. Lists replace the base list.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct EntityOverlay {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<Source>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<EntityKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub links: Option<Vec<EntityLink>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub optional: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<Vec<EntityConfig>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<Vec<EntitySecret>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "scalar_format::deserialize"
+    )]
+    pub instances: Option<usize>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "scalar_format::deserialize"
+    )]
+    pub enabled: Option<bool>,
+}
+
+impl ComponentsConfig {
+    /// Applies the environment `name` (if any) and drops disabled entities
+    pub fn resolve(mut self, name: Option<&str>) -> Result<Self, String> {
+        if let Some(name) = name {
+            let Some(environment) = self.environments.remove(name) else {
+                let known: Vec<_> = self.environments.keys().map(String::as_str).collect();
+                return Err(format!(
+                    "environment {} is not defined (available: {})",
+                    name,
+                    if known.is_empty() {
+                        "none".to_string()
+                    } else {
+                        known.join(", ")
+                    }
+                ));
+            };
+            for overlay in environment.entities {
+                match self.entities.iter_mut().find(|e| e.name == overlay.name) {
+                    Some(entity) => entity.apply(overlay),
+                    None if overlay.source.is_some() => {
+                        let mut entity = Entity {
+                            name: overlay.name.clone(),
+                            ..Default::default()
+                        };
+                        entity.apply(overlay);
+                        self.entities.push(entity);
+                    }
+                    None => {
+                        return Err(format!(
+                            "environment {} patches entity {} which is not in the base file; \
+                             give it a source to add it",
+                            name, overlay.name
+                        ))
+                    }
+                }
+            }
+        }
+
+        self.environments.clear();
+        self.entities
+            .retain(|entity| entity.enabled.unwrap_or(true));
+        Ok(self)
+    }
+}
+
+impl Entity {
+    fn apply(&mut self, overlay: EntityOverlay) {
+        if overlay.source.is_some() {
+            self.source = overlay.source;
+        }
+        if overlay.kind.is_some() {
+            self.kind = overlay.kind;
+        }
+        if let Some(links) = overlay.links {
+            self.links = links;
+        }
+        if let Some(optional) = overlay.optional {
+            self.optional = optional;
+        }
+        if let Some(external) = overlay.external {
+            self.external = external;
+        }
+        if let Some(config) = overlay.config {
+            self.config = config;
+        }
+        if let Some(secrets) = overlay.secrets {
+            self.secrets = secrets;
+        }
+        if overlay.instances.is_some() {
+            self.instances = overlay.instances;
+        }
+        if overlay.enabled.is_some() {
+            self.enabled = overlay.enabled;
+        }
+    }
+}
+
+impl ComponentsConfig {
+    /// Parses the components file `content`, replacing `${VAR}` and `${VAR:-default}` in its
+    /// string values with values from `vars`. Environments other than `env` are dropped
+    /// first, so their variables need not be set. Errors the file has as written are pointed
+    /// at their line and column.
+    pub fn interpolated(
+        path: &Path,
+        content: &str,
+        env: Option<&str>,
+        vars: &HashMap<String, String>,
+    ) -> Result<Self, String> {
+        let located = || parse_components(path, content).err();
+        let mut document: Value = serde_yaml::from_str(content)
+            .map_err(|e| located().unwrap_or_else(|| e.to_string()))?;
+        if let Some(environments) = document
+            .get_mut("environments")
+            .and_then(Value::as_mapping_mut)
+        {
+            environments.retain(|name, _| env.is_some_and(|env| name.as_str() == Some(env)));
+        }
+        interpolate_value(&mut document, "", vars)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        // Parsed from text rather than the value so errors name the offending field
+        let interpolated = serde_yaml::to_string(&document).map_err(|e| e.to_string())?;
+        serde_yaml::from_str(&interpolated).map_err(|e| {
+            let message = e.to_string();
+            let message = message
+                .rsplit_once(" at line ")
+                .map_or(message.as_str(), |(message, _)| message);
+            match located() {
+                Some(error) if error.contains(message) => error,
+                _ => format!("{}: {}", path.display(), message),
+            }
+        })
+    }
+}
+
+/// Interpolates every string scalar under `value`, naming the offending field on errors
+fn interpolate_value(
+    value: &mut Value,
+    field: &str,
+    vars: &HashMap<String, String>,
+) -> Result<(), String> {
+    match value {
+        Value::String(string) => {
+            *string = interpolate(string, vars).map_err(|e| format!("{}: {}", field, e))?;
+        }
+        Value::Sequence(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                interpolate_value(item, &format!("{}[{}]", field, index), vars)?;
+            }
+        }
+        Value::Mapping(mapping) => {
+            for (key, item) in mapping.iter_mut() {
+                let key = key.as_str().unwrap_or("?");
+                let field = if field.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{}.{}", field, key)
+                };
+                interpolate_value(item, &field, vars)?;
+            }
+        }
+        Value::Tagged(tagged) => interpolate_value(&mut tagged.value, field, vars)?,
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+    Ok(())
+}
+
+/// Replaces `${VAR}` and `${VAR:-default}` in `value` with values from `vars`. `$${` is a
+/// literal `${`.
+pub fn interpolate(value: &str, vars: &HashMap<String, String>) -> Result<String, String> {
+    let mut output = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('$') {
+            output.push_str(&rest[..start - 1]);
+            output.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        output.push_str(&rest[..start]);
+
+        let Some(end) = rest[start..].find('}') else {
+            return Err("unterminated ${".to_string());
+        };
+        let expression = &rest[start + 2..start + end];
+        let (var, default) = match expression.split_once(":-") {
+            Some((var, default)) => (var, Some(default)),
+            None => (expression, None),
+        };
+        // Like the shell, `:-` also falls back when the variable is empty
+        match (vars.get(var), default) {
+            (Some(value), Some(default)) if value.is_empty() => output.push_str(default),
+            (Some(value), _) => output.push_str(value),
+            (None, Some(default)) => output.push_str(default),
+            (None, None) => {
+                return Err(format!(
+                    "environment variable {} is not set; use ${{{}:-default}} to provide a \
+                     fallback",
+                    var, var
+                ))
+            }
+        }
+        rest = &rest[start + end + 1..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn interpolate_variables_and_defaults() {
+        let vars = vars(&[("TAG", "1.2.0"), ("EMPTY", "")]);
+        assert_eq!(
+            interpolate("ghcr.io/acme/app:${TAG}", &vars).unwrap(),
+            "ghcr.io/acme/app:1.2.0"
+        );
+        assert_eq!(interpolate("${MISSING:-dev}", &vars).unwrap(), "dev");
+        assert_eq!(
+            interpolate("${EMPTY:-fallback}", &vars).unwrap(),
+            "fallback"
+        );
+        assert_eq!(interpolate("${EMPTY}", &vars).unwrap(), "");
+        assert_eq!(
+            interpolate("$${TAG} ${TAG}", &vars).unwrap(),
+            "${TAG} 1.2.0"
+        );
+        assert_eq!(interpolate("no variables", &vars).unwrap(), "no variables");
+    }
+
+    #[test]
+    fn interpolate_errors() {
+        assert_eq!(
+            interpolate("${TAG}", &HashMap::new()).unwrap_err(),
+            "environment variable TAG is not set; use ${TAG:-default} to provide a fallback"
+        );
+        assert_eq!(
+            interpolate("app:${TAG", &HashMap::new()).unwrap_err(),
+            "unterminated ${"
+        );
+    }
+
+    const COMPONENTS: &str = r#"
+entities:
+  - name: app
+    source:
+      reference: oci://ghcr.io/acme/app:${TAG:-latest}
+    config:
+      - name: settings
+        properties:
+          greeting: "${GREETING}"
+environments:
+  prod:
+    entities:
+      - name: app
+        source:
+          reference: oci://${PROD_REGISTRY}/app:1.0.0
+"#;
+
+    #[test]
+    fn interpolated_keeps_values_strings() {
+        let vars = vars(&[("GREETING", "key: value\n{braces}")]);
+        let config =
+            ComponentsConfig::interpolated(Path::new("components.yaml"), COMPONENTS, None, &vars)
+                .unwrap();
+        let Some(Source::OCI { reference }) = &config.entities[0].source else {
+            panic!("expected an OCI source");
+        };
+        assert_eq!(reference, "ghcr.io/acme/app:latest");
+        assert_eq!(
+            config.entities[0].config[0].properties.as_ref().unwrap()["greeting"],
+            "key: value\n{braces}"
+        );
+        assert!(config.environments.is_empty());
+    }
+
+    #[test]
+    fn interpolated_only_checks_selected_environment() {
+        let vars = vars(&[("GREETING", "hi")]);
+        let error = ComponentsConfig::interpolated(
+            Path::new("components.yaml"),
+            COMPONENTS,
+            Some("prod"),
+            &vars,
+        )
+        .unwrap_err();
+        assert!(error.starts_with(
+            "components.yaml: environments.prod.entities[0].source.reference: environment variable \
+             PROD_REGISTRY"
+        ));
+
+        let missing = ComponentsConfig::interpolated(
+            Path::new("components.yaml"),
+            COMPONENTS,
+            None,
+            &HashMap::new(),
+        );
+        assert!(missing
+            .unwrap_err()
+            .starts_with("components.yaml: entities[0].config[0].properties.greeting:"));
+    }
+
+    #[test]
+    fn interpolated_sources_and_scalars() {
+        let digest = "0acc86472a87f9c43560ec031d45344195f01a4c9572c38c90e5c43569687859";
+        let vars = vars(&[
+            ("DIGEST", digest),
+            ("REF", "oci://ghcr.io/acme/kv:0.3.0"),
+            ("N", "3"),
+            ("ON", "false"),
+        ]);
+        let content = r#"
+entities:
+  - name: app
+    source:
+      url: https://example.com/app.wasm
+      sha256: ${DIGEST}
+    instances: ${N}
+  - name: kv
+    source:
+      reference: ${REF}
+    enabled: ${ON}
+"#;
+        let path = Path::new("components.yaml");
+        let config = ComponentsConfig::interpolated(path, content, None, &vars).unwrap();
+        assert!(matches!(
+            &config.entities[0].source,
+            Some(Source::Url { sha256, .. }) if sha256 == digest
+        ));
+        assert_eq!(config.entities[0].instances, Some(3));
+        assert!(matches!(
+            &config.entities[1].source,
+            Some(Source::OCI { reference }) if reference == "ghcr.io/acme/kv:0.3.0"
+        ));
+        assert_eq!(config.entities[1].enabled, Some(false));
+
+        let vars = HashMap::from([("N".to_string(), "three".to_string())]);
+        let content = "entities:\n  - name: app\n    instances: ${N}\n";
+        let error = ComponentsConfig::interpolated(path, content, None, &vars).unwrap_err();
+        assert_eq!(
+            error,
+            "components.yaml: entities[0]: invalid value \"three\": invalid digit found in string"
+        );
+    }
+
+    #[test]
+    fn interpolated_errors_as_written_are_located() {
+        let content = "entities:\n  - name: app\n    source:\n      path: app.wasm\n";
+        let error =
+            ComponentsConfig::interpolated(Path::new("c.yaml"), content, None, &HashMap::new())
+                .unwrap_err();
+        assert!(error.starts_with("c.yaml:4:7: entities[0].source: path must start with file://"));
+    }
+}
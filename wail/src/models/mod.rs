@@ -1,5 +1,12 @@
 mod components;
-pub use components::{ComponentInfo, ComponentsConfig, Entity, InterfaceInfo, PackageInfo, Source};
+mod environment;
+mod schema;
+pub use components::{
+    ComponentClaims, ComponentInfo, ComponentsConfig, Entity, EntityKind, InterfaceAlias,
+    InterfaceInfo, PackageInfo, PackageRef, RegistryInfo, SecretBackend, Source,
+};
+pub use environment::Environment;
+pub use schema::{components_schema, is_json, parse_components};
 
 /// Represents a uniquely identifiable interface
 #[derive(Clone, Hash, Eq, PartialEq, Debug)]
@@ -9,6 +16,29 @@ pub struct InterfaceIdentifier {
     pub package: String,
 }
 
+impl InterfaceIdentifier {
+    /// Parses `namespace:package/interface`, ignoring an optional `@version` suffix
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.split_once('@').map_or(s, |(unversioned, _)| unversioned);
+        let (namespace, rest) = s.split_once(':')?;
+        let (package, name) = rest.split_once('/')?;
+        if namespace.is_empty() || package.is_empty() || name.is_empty() {
+            return None;
+        }
+        Some(Self {
+            name: name.to_string(),
+            namespace: namespace.to_string(),
+            package: package.to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for InterfaceIdentifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}/{}", self.namespace, self.package, self.name)
+    }
+}
+
 /// Represents an interface requirement (import) or provision (export)
 ///
 /// * `identifier` - Unique identification of the interface (name, namespace, package)
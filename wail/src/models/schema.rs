@@ -0,0 +1,82 @@
+use std::path::Path;
+
+use super::ComponentsConfig;
+
+/// The JSON Schema of the components file
+pub fn components_schema() -> String {
+    let schema = schemars::schema_for!(ComponentsConfig);
+    serde_json::to_string_pretty(&schema).expect("schema serializes to JSON")
+}
+
+/// Parses a components file in YAML or JSON, pointing parse errors at the offending line and
+/// column
+pub fn parse_components(path: &Path, content: &str) -> Result<ComponentsConfig, String> {
+    if is_json(content) {
+        return serde_json::from_str(content).map_err(|e| {
+            // serde_json reports line 0 when the error has no position
+            let location = (e.line() > 0).then(|| (e.line(), e.column()));
+            render_error(path, content, e.to_string(), location)
+        });
+    }
+    serde_yaml::from_str(content).map_err(|e| {
+        let location = e.location().map(|l| (l.line(), l.column()));
+        render_error(path, content, e.to_string(), location)
+    })
+}
+
+/// Whether an input is a JSON document rather than YAML. Every JSON document is also YAML,
+/// but the JSON parser gives more precise errors.
+pub fn is_json(content: &str) -> bool {
+    content.trim_start().starts_with('{')
+}
+
+fn render_error(
+    path: &Path,
+    content: &str,
+    message: String,
+    location: Option<(usize, usize)>,
+) -> String {
+    // The location is rendered separately below
+    let message = message
+        .rsplit_once(" at line ")
+        .map_or(message.as_str(), |(message, _)| message);
+
+    let mut rendered = match location {
+        Some((line, column)) => {
+            let mut rendered = format!("{}:{}:{}: {}\n", path.display(), line, column, message);
+            let width = line.to_string().len();
+            rendered.push_str(&format!("{:width$} |\n", ""));
+            for number in line.saturating_sub(1).max(1)..=line {
+                let text = content.lines().nth(number - 1).unwrap_or_default();
+                rendered.push_str(&format!("{:>width$} | {}\n", number, text));
+            }
+            rendered.push_str(&format!(
+                "{:width$} | {:>column$}\n",
+                "",
+                "^",
+                column = column
+            ));
+            rendered
+        }
+        None => format!("{}: {}\n", path.display(), message),
+    };
+
+    if let Some(hint) = hint(message) {
+        rendered.push_str(&format!("hint: {}\n", hint));
+    }
+    rendered
+}
+
+fn hint(message: &str) -> Option<&'static str> {
+    if message.contains("unknown field") {
+        Some("check the spelling, `wail schema` prints every accepted field")
+    } else if message.contains("missing field") {
+        Some("add the missing field, `wail schema` describes the required fields")
+    } else if message.contains("unknown variant") {
+        Some("use one of the expected values listed above")
+    } else if message.contains("expected a string") {
+        Some("quote values that should be strings, e.g. \"100\" or \"true\"")
+    } else {
+        None
+    }
+}
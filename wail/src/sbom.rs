@@ -0,0 +1,461 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use wadm_types::Properties;
+
+use crate::cli::Wail;
+use crate::core::{ConstructorManifest, LinkConstructor, RUNTIME_INTERFACES};
+use crate::models::InterfaceInfo;
+use crate::WailPlugin;
+
+impl WailPlugin {
+    /// Writes a CycloneDX 1.5 SBOM of the resolved application to `path`.
+    ///
+    /// Components and providers are `application` components carrying their digest, registry
+    /// metadata, and their source, producers and interfaces as `wail:` properties. Every WIT package they import or export
+    /// is a `library` component, and links plus imported packages are recorded as
+    /// dependencies.
+    pub(crate) fn write_sbom(
+        constructor: &ConstructorManifest,
+        args: &Wail,
+        path: &Path,
+    ) -> Result<(), ()> {
+        let sbom = cyclonedx(constructor, args);
+        let content = serde_json::to_string_pretty(&sbom).map_err(|e| {
+            eprintln!("Failed to serialize SBOM: {}", e);
+        })?;
+        std::fs::write(path, content).map_err(|e| {
+            eprintln!("Failed to write SBOM {}: {}", path.display(), e);
+        })?;
+        println!("Wrote SBOM to {}", path.display());
+        Ok(())
+    }
+}
+
+fn cyclonedx(constructor: &ConstructorManifest, args: &Wail) -> Value {
+    let (app_name, app_version) = match &constructor.metadata {
+        Some(metadata) => (
+            metadata.name.clone(),
+            metadata
+                .annotations
+                .get(wadm_types::VERSION_ANNOTATION_KEY)
+                .cloned()
+                .unwrap_or_else(|| args.version.clone()),
+        ),
+        None => (args.name.clone(), args.version.clone()),
+    };
+    let app_ref = format!("app:{}", app_name);
+
+    let mut components = Vec::new();
+    let mut dependencies = Vec::new();
+    let mut packages: BTreeMap<String, (String, Option<String>)> = BTreeMap::new();
+
+    for component in &constructor.spec.components {
+        let (kind, image, application) = match &component.properties {
+            Properties::Component { properties } => {
+                ("component", &properties.image, &properties.application)
+            }
+            Properties::Capability { properties } => {
+                ("capability", &properties.image, &properties.application)
+            }
+        };
+        let info = constructor.component_interfaces.get(&component.name);
+
+        let mut properties = vec![property("wail:kind", kind)];
+        let source = match (image, application) {
+            (Some(image), _) => Some(image.clone()),
+            (None, Some(shared)) => {
+                Some(format!("application:{}/{}", shared.name, shared.component))
+            }
+            (None, None) => None,
+        };
+        if let Some(source) = &source {
+            properties.push(property("wail:source", source));
+        }
+
+        let mut depends_on = BTreeSet::new();
+        if let Some(info) = info {
+            for (field, tools) in &info.producers {
+                for (tool, version) in tools {
+                    properties.push(property(
+                        &format!("wail:producers:{}", field),
+                        format!("{} {}", tool, version).trim_end(),
+                    ));
+                }
+            }
+            for import in &info.imports {
+                properties.push(property("wail:import", &qualified(import)));
+                depends_on.insert(package_ref(import, &mut packages));
+            }
+            for export in &info.exports {
+                properties.push(property("wail:export", &qualified(export)));
+                package_ref(export, &mut packages);
+            }
+        }
+
+        for link in &constructor.link_constructors {
+            if link.pre_component_id != component.name || is_runtime(link) {
+                continue;
+            }
+            let Some(target) = &link.post_component_id else {
+                continue;
+            };
+            for interface in &link.interfaces {
+                properties.push(property(
+                    "wail:link",
+                    &format!(
+                        "{}:{}/{} -> {} ({})",
+                        link.namespace,
+                        link.package,
+                        interface,
+                        target,
+                        link.link_name()
+                    ),
+                ));
+            }
+            depends_on.insert(target.clone());
+        }
+
+        let mut entry = json!({
+            "type": "application",
+            "bom-ref": component.name,
+            "name": component.name,
+            "properties": properties,
+        });
+        // The version the project declares, else the one it was signed with
+        if let Some(version) = info.and_then(|info| {
+            info.version
+                .clone()
+                .or_else(|| info.claims.as_ref()?.version.clone())
+        }) {
+            entry["version"] = json!(version);
+        }
+        if let Some(registry) = info.and_then(|info| info.registry.as_ref()) {
+            if let Some(description) = &registry.description {
+                entry["description"] = json!(description);
+            }
+            if !registry.authors.is_empty() {
+                entry["author"] = json!(registry.authors.join(", "));
+            }
+            if let Some(license) = &registry.license {
+                entry["licenses"] = json!([{ "expression": license }]);
+            }
+            let references: Vec<_> = registry
+                .links
+                .iter()
+                .map(|(ty, url)| json!({ "type": reference_type(ty), "url": url }))
+                .collect();
+            if !references.is_empty() {
+                entry["externalReferences"] = json!(references);
+            }
+        }
+        // Local artifacts and downloaded copies are hashed, other images are identified by
+        // their reference
+        if let Some(digest) = constructor
+            .local_file(&component.name)
+            .and_then(|path| file_digest(&path))
+        {
+            entry["hashes"] = json!([{ "alg": "SHA-256", "content": digest }]);
+        }
+        components.push(entry);
+        dependencies.push(json!({
+            "ref": component.name,
+            "dependsOn": depends_on,
+        }));
+    }
+
+    for (bom_ref, (name, version)) in &packages {
+        let mut entry = json!({
+            "type": "library",
+            "bom-ref": bom_ref,
+            "name": name,
+            "properties": [property("wail:kind", "wit-package")],
+        });
+        if let Some(version) = version {
+            entry["version"] = json!(version);
+        }
+        components.push(entry);
+    }
+
+    dependencies.insert(
+        0,
+        json!({
+            "ref": app_ref,
+            "dependsOn": constructor
+                .spec
+                .components
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<_>>(),
+        }),
+    );
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": timestamp(),
+            "tools": {
+                "components": [{
+                    "type": "application",
+                    "name": "wail",
+                    "version": env!("CARGO_PKG_VERSION"),
+                }],
+            },
+            "component": {
+                "type": "application",
+                "bom-ref": app_ref,
+                "name": app_name,
+                "version": app_version,
+            },
+        },
+        "components": components,
+        "dependencies": dependencies,
+    })
+}
+
+fn property(name: &str, value: &str) -> Value {
+    json!({ "name": name, "value": value })
+}
+
+/// CycloneDX external reference type of a registry metadata link type
+fn reference_type(ty: &str) -> &'static str {
+    match ty {
+        "repository" => "vcs",
+        "homepage" => "website",
+        "documentation" => "documentation",
+        _ => "other",
+    }
+}
+
+fn is_runtime(link: &LinkConstructor) -> bool {
+    link.namespace == "wasi"
+        && RUNTIME_INTERFACES
+            .iter()
+            .any(|(_, pkg, name)| pkg == &link.package && name == &link.interfaces[0])
+}
+
+/// `namespace:package/interface@version`
+fn qualified(interface: &InterfaceInfo) -> String {
+    let mut qualified = format!(
+        "{}:{}/{}",
+        interface.namespace, interface.package, interface.name
+    );
+    if let Some(version) = &interface.version {
+        qualified.push('@');
+        qualified.push_str(version);
+    }
+    qualified
+}
+
+/// Records the WIT package of `interface` and returns its BOM reference
+fn package_ref(
+    interface: &InterfaceInfo,
+    packages: &mut BTreeMap<String, (String, Option<String>)>,
+) -> String {
+    let name = format!("{}:{}", interface.namespace, interface.package);
+    let bom_ref = match &interface.version {
+        Some(version) => format!("wit:{}@{}", name, version),
+        None => format!("wit:{}", name),
+    };
+    packages
+        .entry(bom_ref.clone())
+        .or_insert_with(|| (name, interface.version.clone()));
+    bom_ref
+}
+
+/// SHA-256 of a local artifact, as lowercase hex
+fn file_digest(path: &Path) -> Option<String> {
+    let bytes = std::fs::read(path).ok()?;
+    Some(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// The current time in RFC 3339 format, in UTC
+fn timestamp() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    rfc3339(seconds)
+}
+
+/// `seconds` since the epoch in RFC 3339 format, in UTC
+fn rfc3339(seconds: u64) -> String {
+    let (days, time) = ((seconds / 86_400) as i64, seconds % 86_400);
+
+    // Civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use clap::Parser;
+
+    use super::*;
+    use crate::models::{ComponentInfo, EntityKind};
+
+    fn interface(namespace: &str, package: &str, name: &str, version: &str) -> InterfaceInfo {
+        InterfaceInfo {
+            name: name.to_string(),
+            namespace: namespace.to_string(),
+            package: package.to_string(),
+            version: Some(version.to_string()),
+            functions: Vec::new(),
+            resources_from: Vec::new(),
+        }
+    }
+
+    fn add(
+        manifest: &mut ConstructorManifest,
+        name: &str,
+        image: PathBuf,
+        kind: EntityKind,
+        imports: Vec<InterfaceInfo>,
+        exports: Vec<InterfaceInfo>,
+    ) {
+        let info = ComponentInfo {
+            imports,
+            exports,
+            package: None,
+            claims: None,
+            producers: Default::default(),
+            registry: None,
+            version: None,
+        };
+        manifest
+            .merge_component_info(name.to_string(), info, image, kind)
+            .unwrap();
+    }
+
+    /// Properties named `name` of the SBOM component `bom_ref`
+    fn properties<'a>(sbom: &'a Value, bom_ref: &str, name: &str) -> Vec<&'a str> {
+        let component = sbom["components"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["bom-ref"] == bom_ref)
+            .unwrap();
+        component["properties"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|p| p["name"] == name)
+            .filter_map(|p| p["value"].as_str())
+            .collect()
+    }
+
+    #[test]
+    fn sbom_lists_components_packages_and_links() {
+        let local = std::env::temp_dir().join(format!("wail-sbom-{}.wasm", std::process::id()));
+        std::fs::write(&local, b"\0asm").unwrap();
+        let store = interface("wasi", "keyvalue", "store", "0.2.0-draft");
+        let mut manifest = ConstructorManifest::new();
+        add(
+            &mut manifest,
+            "api",
+            local.clone(),
+            EntityKind::Component,
+            vec![store.clone()],
+            vec![interface("wasi", "http", "incoming-handler", "0.2.0")],
+        );
+        add(
+            &mut manifest,
+            "kv",
+            PathBuf::from("ghcr.io/wasmcloud/keyvalue-redis:0.28.1"),
+            EntityKind::Capability,
+            Vec::new(),
+            vec![store],
+        );
+        manifest.link_constructors.push(LinkConstructor {
+            pre_component_id: "api".to_string(),
+            post_component_id: Some("kv".to_string()),
+            interfaces: vec!["store".to_string()],
+            namespace: "wasi".to_string(),
+            package: "keyvalue".to_string(),
+            link_name: None,
+        });
+
+        let sbom = cyclonedx(&manifest, &Wail::parse_from(["wail", "--name", "shop"]));
+        assert_eq!(sbom["specVersion"], "1.5");
+        assert_eq!(sbom["metadata"]["component"]["bom-ref"], "app:shop");
+
+        let refs: Vec<_> = sbom["components"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| (c["type"].as_str().unwrap(), c["bom-ref"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            refs,
+            [
+                ("application", "api"),
+                ("application", "kv"),
+                ("library", "wit:wasi:http@0.2.0"),
+                ("library", "wit:wasi:keyvalue@0.2.0-draft"),
+            ]
+        );
+        assert_eq!(
+            properties(&sbom, "api", "wail:import"),
+            ["wasi:keyvalue/store@0.2.0-draft"]
+        );
+        assert_eq!(
+            properties(&sbom, "api", "wail:link"),
+            ["wasi:keyvalue/store -> kv (default)"]
+        );
+        assert_eq!(properties(&sbom, "kv", "wail:kind"), ["capability"]);
+
+        // Only the local artifact is hashed
+        assert_eq!(
+            sbom["components"][0]["hashes"][0]["content"],
+            format!("{:x}", Sha256::digest(b"\0asm"))
+        );
+        assert!(sbom["components"][1].get("hashes").is_none());
+
+        assert_eq!(
+            sbom["dependencies"],
+            json!([
+                { "ref": "app:shop", "dependsOn": ["api", "kv"] },
+                { "ref": "api", "dependsOn": ["kv", "wit:wasi:keyvalue@0.2.0-draft"] },
+                { "ref": "kv", "dependsOn": [] },
+            ])
+        );
+    }
+
+    #[test]
+    fn rfc3339_converts_civil_dates() {
+        assert_eq!(rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(rfc3339(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(rfc3339(1_709_251_199), "2024-02-29T23:59:59Z");
+        assert_eq!(rfc3339(1_735_689_599), "2024-12-31T23:59:59Z");
+        assert_eq!(rfc3339(4_102_444_800), "2100-01-01T00:00:00Z");
+    }
+}
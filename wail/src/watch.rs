@@ -0,0 +1,235 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use wadm_types::{Manifest, TraitProperty};
+
+use crate::cli::Wail;
+use crate::core::{
+    file_stamp, registry_manifest_paths, ComponentCache, FileStamp, DEFAULT_LINK_NAME,
+};
+use crate::WailPlugin;
+
+impl WailPlugin {
+    /// Regenerates the manifest every time one of the inputs or decoded components changes.
+    ///
+    /// Decoded interfaces are kept between cycles so only modified artifacts are decoded again.
+    pub(crate) fn watch(args: &Wail) -> Result<(), ()> {
+        let mut cache = Self::component_cache(args);
+        let mut previous_links: Option<BTreeSet<String>> = None;
+        let interval = Duration::from_millis(args.watch_interval);
+
+        loop {
+            match Self::generate(args, &mut cache) {
+                Ok(wadm) => {
+                    Self::write_output(args, &wadm)?;
+                    let links = link_summary(&wadm);
+                    let decoded = cache.take_decoded();
+                    println!();
+                    for line in cycle_summary(&decoded, previous_links.as_ref(), &links) {
+                        println!("{}", line);
+                    }
+                    previous_links = Some(links);
+                }
+                Err(()) => {
+                    cache.take_decoded();
+                    eprintln!("[watch] Generation failed, waiting for changes...");
+                }
+            }
+
+            let stamps = watched_stamps(args, &cache);
+            println!("[watch] Watching {} files for changes", stamps.len());
+            loop {
+                std::thread::sleep(interval);
+                if watched_stamps(args, &cache) != stamps {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Stamps of the WADM input, the components file, shared application manifests, the lint
+/// config, the issuer allowlist and every component artifact seen so far
+fn watched_stamps(args: &Wail, cache: &ComponentCache) -> BTreeMap<PathBuf, Option<FileStamp>> {
+    let registry = args
+        .manifest_registry
+        .as_deref()
+        .and_then(|dir| registry_manifest_paths(dir).ok())
+        .unwrap_or_default();
+
+    args.components
+        .iter()
+        .chain(args.wadm.iter())
+        .chain(args.shared_manifests.iter())
+        .chain(registry.iter())
+        .chain(args.lint_config.iter())
+        .chain(args.alias_rules.iter())
+        .chain(args.issuer_allowlist.iter())
+        .map(PathBuf::as_path)
+        .chain(cache.requested_paths())
+        .map(|path| (path.to_path_buf(), file_stamp(path).ok()))
+        .collect()
+}
+
+/// One line per link trait in the generated manifest
+fn link_summary(wadm: &Manifest) -> BTreeSet<String> {
+    wadm.components()
+        .flat_map(|component| {
+            component
+                .traits
+                .iter()
+                .flatten()
+                .filter_map(move |t| match &t.properties {
+                    TraitProperty::Link(link) => Some(format!(
+                        "{} -> {} ({}:{}/{}, {})",
+                        component.name,
+                        link.target.name,
+                        link.namespace,
+                        link.package,
+                        link.interfaces.join(","),
+                        link.name.as_deref().unwrap_or(DEFAULT_LINK_NAME)
+                    )),
+                    _ => None,
+                })
+        })
+        .collect()
+}
+
+/// What one cycle changed: the decoded components and the links added or removed since the
+/// previous cycle
+fn cycle_summary(
+    decoded: &[PathBuf],
+    previous: Option<&BTreeSet<String>>,
+    current: &BTreeSet<String>,
+) -> Vec<String> {
+    let mut lines = vec![format!(
+        "[watch] Decoded {} changed components",
+        decoded.len()
+    )];
+    for path in decoded {
+        lines.push(format!("  ~ {}", path.display()));
+    }
+
+    let Some(previous) = previous else {
+        lines.push(format!("[watch] {} links", current.len()));
+        return lines;
+    };
+
+    let added: Vec<_> = current.difference(previous).collect();
+    let removed: Vec<_> = previous.difference(current).collect();
+    if added.is_empty() && removed.is_empty() {
+        lines.push(format!("[watch] Links unchanged ({} links)", current.len()));
+        return lines;
+    }
+
+    lines.push(format!(
+        "[watch] Links: +{} -{} ({} total)",
+        added.len(),
+        removed.len(),
+        current.len()
+    ));
+    for link in added {
+        lines.push(format!("  + {}", link));
+    }
+    for link in removed {
+        lines.push(format!("  - {}", link));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+apiVersion: core.oam.dev/v1beta1
+kind: Application
+metadata:
+  name: shop
+spec:
+  components:
+    - name: api
+      type: component
+      properties:
+        image: file://api.wasm
+      traits:
+        - type: spreadscaler
+          properties:
+            instances: 1
+        - type: link
+          properties:
+            namespace: wasi
+            package: keyvalue
+            interfaces: [store, atomics]
+            target:
+              name: kv
+        - type: link
+          properties:
+            namespace: acme
+            package: audit
+            interfaces: [log]
+            name: audit
+            target:
+              name: auditor
+    - name: kv
+      type: capability
+      properties:
+        image: ghcr.io/wasmcloud/keyvalue-redis:0.28.1
+"#;
+
+    fn links(links: &[&str]) -> BTreeSet<String> {
+        links.iter().map(|link| link.to_string()).collect()
+    }
+
+    #[test]
+    fn link_summary_has_one_line_per_link_trait() {
+        let manifest: Manifest = serde_yaml::from_str(MANIFEST).unwrap();
+        assert_eq!(
+            link_summary(&manifest),
+            links(&[
+                "api -> auditor (acme:audit/log, audit)",
+                "api -> kv (wasi:keyvalue/store,atomics, default)",
+            ])
+        );
+    }
+
+    #[test]
+    fn first_cycle_counts_links() {
+        let decoded = [PathBuf::from("api.wasm")];
+        assert_eq!(
+            cycle_summary(&decoded, None, &links(&["a -> b"])),
+            [
+                "[watch] Decoded 1 changed components",
+                "  ~ api.wasm",
+                "[watch] 1 links",
+            ]
+        );
+    }
+
+    #[test]
+    fn later_cycles_diff_links() {
+        let previous = links(&["a -> b", "a -> c"]);
+        assert_eq!(
+            cycle_summary(&[], Some(&previous), &previous),
+            [
+                "[watch] Decoded 0 changed components",
+                "[watch] Links unchanged (2 links)",
+            ]
+        );
+        assert_eq!(
+            cycle_summary(
+                &[],
+                Some(&previous),
+                &links(&["a -> b", "a -> d", "a -> e"])
+            ),
+            [
+                "[watch] Decoded 0 changed components",
+                "[watch] Links: +2 -1 (3 total)",
+                "  + a -> d",
+                "  + a -> e",
+                "  - a -> c",
+            ]
+        );
+    }
+}